@@ -0,0 +1,438 @@
+//! Defines the command-line interface of the extractor.
+
+use crate::db::SongSortField;
+use clap::{Args, FromArgMatches, Parser, Subcommand, ValueEnum};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use tunecore::models::ReleaseDate;
+
+/// Collects TuneCore community songs into MongoDB.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// The command to run. Defaults to `collect`.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// The commands supported by the extractor.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Crawls all community songs and saves them to the database.
+    Collect(CollectArgs),
+    /// Inspects the recorded ingestion runs.
+    #[command(subcommand)]
+    Runs(RunsCommand),
+    /// Reports the share rate changes observed by an ingestion run.
+    ShareReport(ShareReportCommandArgs),
+    /// Compares the database indexes with the declared ones.
+    Indexes {
+        /// Prints the drift as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Resolves and reviews artist identities.
+    #[command(subcommand)]
+    Artists(ArtistsCommand),
+    /// Queries the stored songs.
+    #[command(subcommand)]
+    Songs(SongsCommand),
+    /// Exports the artist collaboration graph built from song credits.
+    Collaborations(CollaborationsArgs),
+    /// Applies pending database migrations.
+    Migrate {
+        /// Reports what each pending migration would change without writing.
+        #[arg(long, conflicts_with = "status")]
+        dry_run: bool,
+        /// Lists the applied and pending migrations instead of running them.
+        #[arg(long)]
+        status: bool,
+        /// Prints the result as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Arguments for the `collect` command.
+#[derive(Args, Debug)]
+pub struct CollectArgs {
+    /// The maximum number of pages fetched concurrently.
+    #[arg(long, default_value = "25")]
+    pub concurrency: NonZeroUsize,
+
+    /// Ignores any saved checkpoint and crawls every page again.
+    #[arg(long, conflicts_with = "retry_failed")]
+    pub fresh: bool,
+
+    /// Re-fetches only the pages recorded as failed by earlier crawls.
+    #[arg(long)]
+    pub retry_failed: bool,
+
+    /// Crawls the songs sorted by popularity and records each song's rank.
+    #[arg(long)]
+    pub popularity: bool,
+
+    /// How to report the share rate changes of the run.
+    #[command(flatten)]
+    pub share_report: ShareReportArgs,
+}
+
+/// Options for share rate reports, shared by `collect` and `share-report`.
+#[derive(Args, Debug)]
+pub struct ShareReportArgs {
+    /// Also reports songs whose share rate crossed this percentage.
+    #[arg(long, env = "SHARE_RATE_THRESHOLD")]
+    pub share_threshold: Option<f64>,
+
+    /// Writes the report as JSON and Markdown files into this directory.
+    #[arg(long, env = "SHARE_RATE_REPORT_DIR")]
+    pub share_report_dir: Option<PathBuf>,
+
+    /// Posts the report to this webhook URL when any share rate changed.
+    #[arg(long, env = "SHARE_RATE_WEBHOOK_URL")]
+    pub share_webhook: Option<String>,
+}
+
+/// Arguments for the `share-report` command.
+#[derive(Args, Debug)]
+pub struct ShareReportCommandArgs {
+    /// The ID of the run to report on. Defaults to the most recent completed `collect` run.
+    #[arg(long)]
+    pub run_id: Option<String>,
+
+    /// The format the report is printed in.
+    #[arg(long, value_enum, default_value_t = ReportFormat::Markdown)]
+    pub format: ReportFormat,
+
+    /// Where else to send the report.
+    #[command(flatten)]
+    pub share_report: ShareReportArgs,
+}
+
+/// The formats reports can be printed in.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ReportFormat {
+    /// Pretty-printed JSON.
+    Json,
+    /// A Markdown document.
+    Markdown,
+}
+
+/// Arguments for the `collaborations` command.
+#[derive(Args, Debug)]
+pub struct CollaborationsArgs {
+    /// The format the graph is exported in.
+    #[arg(long, value_enum, default_value_t = GraphFormat::Summary)]
+    pub format: GraphFormat,
+
+    /// Writes the export to this file instead of printing it.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// The number of shared songs two artists need to be connected.
+    #[arg(long, default_value_t = 1)]
+    pub min_weight: u64,
+
+    /// The number of best connected artists listed in the summary.
+    #[arg(long, default_value_t = 20)]
+    pub top: usize,
+}
+
+/// The formats the collaboration graph can be exported in.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum GraphFormat {
+    /// The graph metrics and the best connected artists.
+    Summary,
+    /// Pretty-printed JSON with nodes, edges and metrics.
+    Json,
+    /// A GraphML document.
+    Graphml,
+    /// A Graphviz DOT graph.
+    Dot,
+}
+
+/// The subcommands of the `runs` command.
+#[derive(Subcommand, Debug)]
+pub enum RunsCommand {
+    /// Lists the most recent runs, newest first.
+    List {
+        /// The maximum number of runs to list.
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+
+        /// Prints the runs as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Prints the summary of a single run.
+    Show {
+        /// The ID of the run.
+        run_id: String,
+
+        /// Prints the run as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl Default for CollectArgs {
+    /// Returns the arguments of `collect` when it runs without options.
+    fn default() -> Self {
+        let matches =
+            Self::augment_args(clap::Command::new("collect")).get_matches_from(["collect"]);
+        Self::from_arg_matches(&matches).expect("the defaults of `collect` are valid")
+    }
+}
+
+/// Subcommands of the `artists` command.
+#[derive(Subcommand, Debug)]
+pub enum ArtistsCommand {
+    /// Clusters the stored artist records into canonical artists.
+    Resolve {
+        /// Prints the entities as JSON instead of a summary.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Lists the least confident clusters of several artist records and the
+    /// records with weaker matches that were not merged.
+    Review {
+        /// The highest confidence of a cluster to list.
+        #[arg(long, default_value_t = 0.9)]
+        max_confidence: f64,
+
+        /// The maximum number of clusters to list.
+        #[arg(long, default_value_t = 50)]
+        limit: u32,
+
+        /// Prints the clusters as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Subcommands of the `songs` command.
+#[derive(Subcommand, Debug)]
+pub enum SongsCommand {
+    /// Prints the stored songs matching a filter as JSON lines.
+    Search(SongSearchArgs),
+    /// Counts the songs released per week or month.
+    Calendar {
+        /// The length of the periods to count in.
+        #[arg(long, value_enum, default_value_t = CalendarBy::Month)]
+        by: CalendarBy,
+
+        /// The earliest release date to count, as `YYYY-MM-DD`.
+        #[arg(long)]
+        from: Option<ReleaseDate>,
+
+        /// The latest release date to count, as `YYYY-MM-DD`.
+        #[arg(long)]
+        to: Option<ReleaseDate>,
+
+        /// Prints the counts as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Lists the songs released in a date range.
+    Released {
+        /// The earliest release date to list, as `YYYY-MM-DD`.
+        #[arg(long)]
+        from: ReleaseDate,
+
+        /// The latest release date to list, as `YYYY-MM-DD`.
+        #[arg(long)]
+        to: ReleaseDate,
+
+        /// Prints the songs as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Lists the songs that are not released yet, soonest first.
+    Upcoming {
+        /// The maximum number of songs to list.
+        #[arg(long, default_value_t = 50)]
+        limit: u32,
+
+        /// Prints the songs as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Prints one page of songs and the token for the next page as JSON.
+    List {
+        /// The token printed as `next` by the previous page. Starts at the first page if omitted.
+        #[arg(long)]
+        after: Option<String>,
+
+        /// The field to sort by. Ignored when continuing after a token.
+        #[arg(long, value_enum, default_value_t = SongSortKey::Id)]
+        sort: SongSortKey,
+
+        /// Sorts from the highest value to the lowest. Ignored when continuing after a token.
+        #[arg(long)]
+        desc: bool,
+
+        /// The maximum number of songs on the page.
+        #[arg(long, default_value_t = 100)]
+        limit: u32,
+    },
+}
+
+/// Arguments for the `songs search` command.
+#[derive(Args, Debug)]
+pub struct SongSearchArgs {
+    /// Matches songs in any of these genres.
+    #[arg(long = "genre", value_delimiter = ',')]
+    pub genres: Vec<u16>,
+
+    /// Matches songs with any of these moods.
+    #[arg(long = "mood", value_delimiter = ',')]
+    pub moods: Vec<u16>,
+
+    /// The lowest BPM to match.
+    #[arg(long)]
+    pub bpm_min: Option<f32>,
+
+    /// The highest BPM to match.
+    #[arg(long)]
+    pub bpm_max: Option<f32>,
+
+    /// The shortest duration to match, in seconds.
+    #[arg(long)]
+    pub duration_min: Option<f32>,
+
+    /// The longest duration to match, in seconds.
+    #[arg(long)]
+    pub duration_max: Option<f32>,
+
+    /// The lowest share rate to match, in percent.
+    #[arg(long)]
+    pub share_min: Option<f64>,
+
+    /// The highest share rate to match, in percent.
+    #[arg(long)]
+    pub share_max: Option<f64>,
+
+    /// Matches songs crediting this artist.
+    #[arg(long)]
+    pub artist: Option<u64>,
+
+    /// The earliest release date to match, as `YYYY-MM-DD`.
+    #[arg(long)]
+    pub released_from: Option<ReleaseDate>,
+
+    /// The latest release date to match, as `YYYY-MM-DD`.
+    #[arg(long)]
+    pub released_to: Option<ReleaseDate>,
+
+    /// Matches songs whose title or artist names contain this text.
+    #[arg(long)]
+    pub text: Option<String>,
+
+    /// Also matches songs that are no longer listed.
+    #[arg(long)]
+    pub include_removed: bool,
+
+    /// The field to sort by.
+    #[arg(long, value_enum, default_value_t = SongSortKey::Id)]
+    pub sort: SongSortKey,
+
+    /// Sorts from the highest value to the lowest.
+    #[arg(long)]
+    pub desc: bool,
+
+    /// The fields to print, such as `id,song_title.ja`. Prints all fields if omitted.
+    #[arg(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+
+    /// The maximum number of songs to print.
+    #[arg(long)]
+    pub limit: Option<u32>,
+}
+
+/// The fields songs can be sorted by.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SongSortKey {
+    /// The song ID.
+    Id,
+    /// The release date.
+    StreetDate,
+    /// The beats per minute.
+    Bpm,
+    /// The duration.
+    Duration,
+    /// When the song was first saved.
+    FirstSeen,
+    /// When the song was last saved.
+    LastSeen,
+}
+
+impl From<SongSortKey> for SongSortField {
+    fn from(key: SongSortKey) -> Self {
+        match key {
+            SongSortKey::Id => SongSortField::Id,
+            SongSortKey::StreetDate => SongSortField::StreetDate,
+            SongSortKey::Bpm => SongSortField::Bpm,
+            SongSortKey::Duration => SongSortField::Duration,
+            SongSortKey::FirstSeen => SongSortField::FirstSeenAt,
+            SongSortKey::LastSeen => SongSortField::LastSeenAt,
+        }
+    }
+}
+
+/// The periods releases can be counted in.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum CalendarBy {
+    /// Weeks starting on Monday.
+    Week,
+    /// Calendar months.
+    Month,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_args(args: &[&str]) -> Result<CollectArgs, clap::Error> {
+        let args = ["extractor", "collect"].iter().chain(args);
+        match Cli::try_parse_from(args)?.command {
+            Some(Command::Collect(args)) => Ok(args),
+            command => panic!("parsed {command:?} instead of `collect`"),
+        }
+    }
+
+    #[test]
+    fn concurrency_must_be_positive() {
+        let args = collect_args(&["--concurrency", "4"]).unwrap();
+        assert_eq!(args.concurrency.get(), 4);
+        assert!(collect_args(&["--concurrency", "0"]).is_err());
+    }
+
+    #[test]
+    fn collect_defaults_match_the_parsed_defaults() {
+        let parsed = collect_args(&[]).unwrap();
+        let default = CollectArgs::default();
+
+        assert_eq!(default.concurrency.get(), 25);
+        assert_eq!(default.concurrency, parsed.concurrency);
+        assert_eq!(default.fresh, parsed.fresh);
+        assert_eq!(default.retry_failed, parsed.retry_failed);
+        assert_eq!(default.popularity, parsed.popularity);
+    }
+
+    #[test]
+    fn sort_keys_convert_to_the_stored_fields() {
+        let parse = |name| SongSortKey::from_str(name, false).map(SongSortField::from);
+
+        assert_eq!(parse("street-date"), Ok(SongSortField::StreetDate));
+        assert_eq!(parse("first-seen"), Ok(SongSortField::FirstSeenAt));
+        assert_eq!(parse("last-seen"), Ok(SongSortField::LastSeenAt));
+        assert!(parse("last_seen_at").is_err());
+    }
+
+    #[test]
+    fn the_interface_is_consistent() {
+        use clap::CommandFactory;
+
+        Cli::command().debug_assert();
+    }
+}
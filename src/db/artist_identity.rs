@@ -0,0 +1,456 @@
+//! Resolves artist records into canonical artist entities.
+//!
+//! The same real artist can appear under several `artist_id`s. Records are
+//! linked when they share a `common_artist_id` or at least two different
+//! normalized names (ja, en or kana), and linked records form a cluster. A
+//! single shared name is too weak to merge on, since different artists often
+//! share one; such links are kept as candidates for manual review instead. A
+//! name shared under several kinds, like a Latin name used as both the ja and
+//! the en name, is still a single name.
+//! Each link is weighted by its evidence, and a cluster is only as
+//! trustworthy as the weakest link that holds it together, so that is its
+//! confidence.
+
+use super::artists_repo::StoredArtist;
+use super::release_date;
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tunecore::models::{ArtistName, ReleaseDate};
+
+/// The evidence that two artist records belong to the same artist.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchReason {
+    /// Both records link to the same common artist.
+    CommonArtist,
+    /// Both records have the same normalized Japanese name.
+    JaName,
+    /// Both records have the same normalized English name.
+    EnName,
+    /// Both records have the same normalized kana name.
+    KanaName,
+}
+
+impl MatchReason {
+    /// Returns `true` if the values two records share are enough to merge
+    /// them: a common artist or at least two different names.
+    fn is_enough_to_merge(shared: &[(MatchReason, String)]) -> bool {
+        shared
+            .iter()
+            .any(|(reason, _)| *reason == MatchReason::CommonArtist)
+            || shared
+                .iter()
+                .map(|(_, value)| value)
+                .collect::<HashSet<_>>()
+                .len()
+                >= 2
+    }
+
+    /// Returns the combined weight of the values two records share, between
+    /// 0 and 1. A value shared under several reasons counts once, with the
+    /// weight of its strongest reason.
+    fn confidence(shared: &[(MatchReason, String)]) -> f64 {
+        let mut weights: HashMap<&str, f64> = HashMap::new();
+        for (reason, value) in shared {
+            let weight = weights.entry(value).or_default();
+            *weight = weight.max(reason.weight());
+        }
+        1.0 - weights
+            .into_values()
+            .map(|weight| 1.0 - weight)
+            .product::<f64>()
+    }
+
+    /// Returns how strongly this evidence alone links two records.
+    fn weight(self) -> f64 {
+        match self {
+            MatchReason::CommonArtist => 1.0,
+            MatchReason::JaName => 0.7,
+            MatchReason::EnName => 0.6,
+            MatchReason::KanaName => 0.5,
+        }
+    }
+}
+
+/// A link between two artist records.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArtistLink {
+    /// The ID of one linked artist.
+    pub from: u64,
+    /// The ID of the other linked artist.
+    pub to: u64,
+    /// Every piece of evidence shared by the two records.
+    pub reasons: Vec<MatchReason>,
+    /// The combined weight of the evidence, between 0 and 1.
+    pub confidence: f64,
+}
+
+/// An artist record that is part of an entity.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EntityMember {
+    /// The ID of the artist record.
+    pub artist_id: u64,
+    /// The localized names of the record.
+    pub name: ArtistName,
+    /// The common artist the record links to, if any.
+    pub common_artist_id: Option<u64>,
+    /// The number of stored songs crediting the record.
+    pub song_count: u64,
+}
+
+/// A canonical artist made of one or more artist records.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArtistEntity {
+    /// The ID of the entity: the lowest `artist_id` among its members.
+    #[serde(rename = "_id")]
+    pub id: u64,
+    /// The names of the member credited on the most songs.
+    pub name: ArtistName,
+    /// The artist records that make up the entity.
+    pub members: Vec<EntityMember>,
+    /// The links that join the members; the strongest ones that connect them all.
+    pub links: Vec<ArtistLink>,
+    /// The confidence of the weakest link, or 1 for a single record.
+    pub confidence: f64,
+    /// Links to records of other entities that are too weak to merge on.
+    #[serde(default)]
+    pub candidates: Vec<ArtistLink>,
+    /// The number of stored songs crediting any member.
+    pub song_count: u64,
+    /// The earliest release date among the members' songs.
+    #[serde(default, with = "release_date::option")]
+    pub first_release_date: Option<ReleaseDate>,
+    /// The latest release date among the members' songs.
+    #[serde(default, with = "release_date::option")]
+    pub last_release_date: Option<ReleaseDate>,
+    /// When the entity was resolved.
+    pub resolved_at: DateTime,
+}
+
+/// Normalizes a name for matching.
+///
+/// Full-width ASCII is folded to half-width, katakana to hiragana, letters
+/// are lowercased, and anything that is not a letter or digit is dropped.
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Clusters artist records into entities.
+///
+/// # Returns
+/// The entities, ordered by ID.
+pub(super) fn resolve(artists: &[StoredArtist], resolved_at: DateTime) -> Vec<ArtistEntity> {
+    let mut groups: HashMap<(MatchReason, String), Vec<usize>> = HashMap::new();
+    for (index, artist) in artists.iter().enumerate() {
+        let mut keys = Vec::new();
+        if let Some(common_artist_id) = artist.common_artist_id {
+            keys.push((MatchReason::CommonArtist, common_artist_id.to_string()));
+        }
+        keys.push((MatchReason::JaName, normalize_name(&artist.name.ja)));
+        if let Some(en) = &artist.name.en {
+            keys.push((MatchReason::EnName, normalize_name(en)));
+        }
+        if let Some(ja_kana) = &artist.name.ja_kana {
+            keys.push((MatchReason::KanaName, normalize_name(ja_kana)));
+        }
+        for key in keys.into_iter().filter(|(_, value)| !value.is_empty()) {
+            groups.entry(key).or_default().push(index);
+        }
+    }
+
+    let mut evidence: BTreeMap<(usize, usize), Vec<(MatchReason, String)>> = BTreeMap::new();
+    for ((reason, value), members) in groups {
+        for (position, &a) in members.iter().enumerate() {
+            for &b in &members[position + 1..] {
+                evidence
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push((reason, value.clone()));
+            }
+        }
+    }
+
+    let mut candidates: Vec<(usize, usize, Vec<MatchReason>, f64, bool)> = evidence
+        .into_iter()
+        .map(|((a, b), mut shared)| {
+            shared.sort_unstable();
+            let confidence = MatchReason::confidence(&shared);
+            let enough = MatchReason::is_enough_to_merge(&shared);
+            let reasons = shared.into_iter().map(|(reason, _)| reason).collect();
+            (a, b, reasons, confidence, enough)
+        })
+        .collect();
+    candidates.sort_by(|left, right| right.3.total_cmp(&left.3));
+
+    let mut parents: Vec<usize> = (0..artists.len()).collect();
+    let mut links: Vec<(usize, ArtistLink)> = Vec::new();
+    let mut weak: Vec<(usize, usize, ArtistLink)> = Vec::new();
+    for (a, b, reasons, confidence, enough) in candidates {
+        let link = ArtistLink {
+            from: artists[a].artist_id,
+            to: artists[b].artist_id,
+            reasons,
+            confidence,
+        };
+        if !enough {
+            weak.push((a, b, link));
+            continue;
+        }
+        let (root_a, root_b) = (find(&mut parents, a), find(&mut parents, b));
+        if root_a == root_b {
+            continue;
+        }
+        parents[root_b] = root_a;
+        links.push((a, link));
+    }
+
+    let mut clusters: BTreeMap<usize, Cluster> = BTreeMap::new();
+    for index in 0..artists.len() {
+        let root = find(&mut parents, index);
+        clusters.entry(root).or_default().members.push(index);
+    }
+    for (index, link) in links {
+        let root = find(&mut parents, index);
+        clusters.entry(root).or_default().links.push(link);
+    }
+    for (a, b, link) in weak {
+        let root = find(&mut parents, a);
+        if root != find(&mut parents, b) {
+            clusters.entry(root).or_default().candidates.push(link);
+        }
+    }
+
+    let mut entities: Vec<ArtistEntity> = clusters
+        .into_values()
+        .map(|cluster| entity(artists, cluster, resolved_at))
+        .collect();
+    entities.sort_unstable_by_key(|entity| entity.id);
+    entities
+}
+
+/// The records and links of one entity while it is being resolved.
+#[derive(Default)]
+struct Cluster {
+    /// The indexes of the member records.
+    members: Vec<usize>,
+    /// The links that merged the members.
+    links: Vec<ArtistLink>,
+    /// The weak links to records of other clusters.
+    candidates: Vec<ArtistLink>,
+}
+
+/// Builds the entity for a cluster of artist records.
+fn entity(artists: &[StoredArtist], cluster: Cluster, resolved_at: DateTime) -> ArtistEntity {
+    let Cluster {
+        members,
+        links,
+        candidates,
+    } = cluster;
+    let records: Vec<&StoredArtist> = members.iter().map(|&index| &artists[index]).collect();
+    let primary = records
+        .iter()
+        .max_by_key(|artist| (artist.song_count, std::cmp::Reverse(artist.artist_id)))
+        .expect("clusters have at least one member");
+
+    let mut members: Vec<EntityMember> = records
+        .iter()
+        .map(|artist| EntityMember {
+            artist_id: artist.artist_id,
+            name: artist.name.clone(),
+            common_artist_id: artist.common_artist_id,
+            song_count: artist.song_count,
+        })
+        .collect();
+    members.sort_unstable_by_key(|member| member.artist_id);
+
+    ArtistEntity {
+        id: members[0].artist_id,
+        name: primary.name.clone(),
+        confidence: links.iter().map(|link| link.confidence).fold(1.0, f64::min),
+        links,
+        candidates,
+        members,
+        song_count: records.iter().map(|artist| artist.song_count).sum(),
+        first_release_date: records
+            .iter()
+            .filter_map(|artist| artist.first_release_date)
+            .min(),
+        last_release_date: records
+            .iter()
+            .filter_map(|artist| artist.last_release_date)
+            .max(),
+        resolved_at,
+    }
+}
+
+/// Finds the root of a record in the union-find forest, compressing the path.
+fn find(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut current = index;
+    while parents[current] != root {
+        let next = parents[current];
+        parents[current] = root;
+        current = next;
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artist(artist_id: u64, ja: &str, en: Option<&str>, kana: Option<&str>) -> StoredArtist {
+        StoredArtist {
+            artist_id,
+            name: ArtistName {
+                ja: ja.to_owned(),
+                en: en.map(str::to_owned),
+                ja_kana: kana.map(str::to_owned),
+            },
+            is_common_artist: false,
+            is_artist_page_available: false,
+            artist_page_path: String::new(),
+            common_artist_id: None,
+            song_count: artist_id,
+            first_release_date: None,
+            last_release_date: None,
+            first_seen_at: DateTime::from_millis(0),
+            last_seen_at: DateTime::from_millis(0),
+        }
+    }
+
+    fn member_ids(entity: &ArtistEntity) -> Vec<u64> {
+        entity
+            .members
+            .iter()
+            .map(|member| member.artist_id)
+            .collect()
+    }
+
+    #[test]
+    fn names_are_normalized_for_matching() {
+        assert_eq!(normalize_name("ＡＢＣ　１２３"), "abc123");
+        assert_eq!(normalize_name("カタカナ"), "かたかな");
+        assert_eq!(normalize_name("The Band!"), "theband");
+        assert_eq!(normalize_name(" - "), "");
+    }
+
+    #[test]
+    fn a_common_artist_merges_records() {
+        let mut first = artist(1, "Alpha", None, None);
+        let mut second = artist(2, "Beta", None, None);
+        first.common_artist_id = Some(9);
+        second.common_artist_id = Some(9);
+
+        let entities = resolve(&[first, second], DateTime::from_millis(0));
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(member_ids(&entities[0]), vec![1, 2]);
+        assert_eq!(entities[0].confidence, 1.0);
+        assert_eq!(
+            entities[0].links[0].reasons,
+            vec![MatchReason::CommonArtist]
+        );
+    }
+
+    #[test]
+    fn two_kinds_of_name_merge_records() {
+        let artists = [
+            artist(1, "アルファ", Some("Alpha"), None),
+            artist(2, "あるふぁ", Some("ALPHA"), None),
+        ];
+
+        let entities = resolve(&artists, DateTime::from_millis(0));
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(
+            entities[0].links[0].reasons,
+            vec![MatchReason::JaName, MatchReason::EnName]
+        );
+        assert!((entities[0].confidence - 0.88).abs() < 1e-9);
+        assert_eq!(entities[0].name.en.as_deref(), Some("ALPHA"));
+    }
+
+    #[test]
+    fn a_single_shared_name_is_only_a_candidate() {
+        let artists = [
+            artist(1, "Alpha", None, None),
+            artist(2, "alpha", Some("Other"), None),
+        ];
+
+        let entities = resolve(&artists, DateTime::from_millis(0));
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].candidates.len(), 1);
+        assert_eq!(entities[0].candidates[0].to, 2);
+        assert_eq!(entities[0].candidates[0].reasons, vec![MatchReason::JaName]);
+        assert!(entities[1].candidates.is_empty());
+    }
+
+    #[test]
+    fn a_name_shared_under_several_kinds_is_only_a_candidate() {
+        let artists = [
+            artist(1, "Nova", Some("Nova"), None),
+            artist(2, "NOVA", Some("nova"), None),
+            artist(3, "ミナ", None, Some("ミナ")),
+            artist(4, "みな", None, Some("みな")),
+        ];
+
+        let entities = resolve(&artists, DateTime::from_millis(0));
+
+        assert_eq!(entities.len(), 4);
+        assert_eq!(
+            entities[0].candidates[0].reasons,
+            vec![MatchReason::JaName, MatchReason::EnName]
+        );
+        assert!((entities[0].candidates[0].confidence - 0.7).abs() < 1e-9);
+        assert_eq!(
+            entities[2].candidates[0].reasons,
+            vec![MatchReason::JaName, MatchReason::KanaName]
+        );
+    }
+
+    #[test]
+    fn candidates_within_an_entity_are_dropped() {
+        let mut first = artist(1, "Alpha", None, None);
+        let mut second = artist(2, "Beta", None, None);
+        let third = artist(3, "Beta", None, None);
+        first.common_artist_id = Some(9);
+        second.common_artist_id = Some(9);
+        let mut fourth = artist(4, "Alpha", None, None);
+        fourth.common_artist_id = Some(9);
+
+        let entities = resolve(&[first, second, third, fourth], DateTime::from_millis(0));
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(member_ids(&entities[0]), vec![1, 2, 4]);
+        let candidates: Vec<(u64, u64)> = entities
+            .iter()
+            .flat_map(|entity| &entity.candidates)
+            .map(|link| (link.from, link.to))
+            .collect();
+        assert_eq!(candidates, vec![(2, 3)]);
+    }
+
+    #[test]
+    fn empty_names_do_not_link() {
+        let artists = [artist(1, "!!", None, None), artist(2, "??", None, None)];
+
+        let entities = resolve(&artists, DateTime::from_millis(0));
+
+        assert_eq!(entities.len(), 2);
+        assert!(entities.iter().all(|entity| entity.candidates.is_empty()));
+    }
+}
@@ -0,0 +1,411 @@
+//! Contains the repository logic for the `artists` collection.
+//! Songs repeat the full record of every credited artist, so each artist is
+//! also kept once here, keyed by `artist_id`, together with statistics
+//! derived from the songs that credit them. Records of the same real artist
+//! are resolved into the entities stored in `artist_entities`.
+
+use super::artist_identity::{self, ArtistEntity};
+use super::{collections, indexes, release_date, DbResult};
+use futures_util::stream::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, DateTime, Document},
+    options::FindOptions,
+    Collection, Database,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use tunecore::models::{ArtistName, CommunitySong, ReleaseDate};
+
+/// An artist as it is persisted in the `artists` collection.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredArtist {
+    /// The unique identifier for the artist.
+    pub artist_id: u64,
+    /// The localized names of the artist.
+    pub name: ArtistName,
+    /// Indicates if this is a common artist entity.
+    pub is_common_artist: bool,
+    /// Indicates if a dedicated page for this artist is available.
+    pub is_artist_page_available: bool,
+    /// The URL path to the artist's page.
+    pub artist_page_path: String,
+    /// The ID of the common artist, if applicable.
+    pub common_artist_id: Option<u64>,
+    /// The number of stored songs crediting the artist.
+    pub song_count: u64,
+    /// The earliest release date among those songs.
+    #[serde(default, with = "release_date::option")]
+    pub first_release_date: Option<ReleaseDate>,
+    /// The latest release date among those songs.
+    #[serde(default, with = "release_date::option")]
+    pub last_release_date: Option<ReleaseDate>,
+    /// When a song crediting the artist was first saved.
+    pub first_seen_at: DateTime,
+    /// When a song crediting the artist was last saved.
+    pub last_seen_at: DateTime,
+}
+
+/// A repository for handling database operations on the `artists` collection.
+#[derive(Clone, Debug)]
+pub struct ArtistsRepo {
+    database: Database,
+    collection: Collection<StoredArtist>,
+    entities: Collection<ArtistEntity>,
+}
+
+impl ArtistsRepo {
+    /// Creates a new `ArtistsRepo`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the `mongodb::Database` instance.
+    pub(super) fn new(db: &Database) -> Self {
+        Self {
+            database: db.clone(),
+            collection: db.collection(collections::ARTISTS),
+            entities: db.collection(collections::ARTIST_ENTITIES),
+        }
+    }
+
+    /// Upserts every artist credited on the given songs.
+    ///
+    /// The songs must already be saved: each artist is rebuilt from all
+    /// stored songs crediting them, taking the profile from the most recently
+    /// seen song so the song count and release dates stay exact no matter how
+    /// often a song is saved again.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the number of distinct artists upserted.
+    pub async fn upsert_from_songs(&self, songs: &[CommunitySong]) -> DbResult<usize> {
+        let artist_ids: BTreeSet<i64> = songs
+            .iter()
+            .flat_map(|song| &song.artists)
+            .map(|artist| artist.artist_id as i64)
+            .collect();
+        if artist_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let artist_ids: Vec<i64> = artist_ids.into_iter().collect();
+        self.rebuild(Some(&artist_ids)).await?;
+        Ok(artist_ids.len())
+    }
+
+    /// Creates the declared indexes of the `artists` collection that do not
+    /// exist yet.
+    ///
+    /// [`Self::rebuild`] depends on the unique index on `artist_id`, so
+    /// migrations, which run before the schema is prepared, call this first.
+    pub(super) async fn ensure_indexes(&self) -> DbResult<()> {
+        indexes::create_declared(&self.database, collections::ARTISTS, collections::ARTISTS).await
+    }
+
+    /// Rebuilds the artists credited on the songs marked as removed at or
+    /// after `since`, so they no longer count those songs.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the number of distinct artists rebuilt.
+    pub async fn rebuild_removed(&self, since: DateTime) -> DbResult<usize> {
+        let artist_ids: BTreeSet<i64> = self
+            .database
+            .collection::<Document>(collections::SONGS)
+            .distinct(
+                "artists.artist_id",
+                doc! { "removed_at": { "$gte": since } },
+            )
+            .await?
+            .iter()
+            .filter_map(|artist_id| match artist_id {
+                Bson::Int64(artist_id) => Some(*artist_id),
+                Bson::Int32(artist_id) => Some(i64::from(*artist_id)),
+                _ => None,
+            })
+            .collect();
+        if artist_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let artist_ids: Vec<i64> = artist_ids.into_iter().collect();
+        self.rebuild(Some(&artist_ids)).await?;
+        Ok(artist_ids.len())
+    }
+
+    /// Rebuilds the given artists, or every credited artist if `artist_ids`
+    /// is `None`, from the stored songs that are not marked as removed.
+    ///
+    /// This runs a single aggregation over the `songs` collection that merges
+    /// its results into `artists`, relying on the unique index on `artist_id`.
+    /// Artists no longer credited on any listed song are kept, with no songs
+    /// and no release dates.
+    pub(super) async fn rebuild(&self, artist_ids: Option<&[i64]>) -> DbResult<()> {
+        let songs = self.database.collection::<Document>(collections::SONGS);
+        songs.aggregate(rebuild_pipeline(artist_ids)).await?;
+
+        let listed_ids = songs
+            .distinct("artists.artist_id", listed_songs_filter(artist_ids))
+            .await?;
+        self.collection
+            .update_many(
+                unlisted_artists_filter(artist_ids, listed_ids),
+                doc! { "$set": {
+                    "song_count": 0,
+                    "first_release_date": null,
+                    "last_release_date": null,
+                } },
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Retrieves an artist by ID.
+    #[allow(dead_code)]
+    pub async fn find(&self, artist_id: u64) -> DbResult<Option<StoredArtist>> {
+        let artist = self
+            .collection
+            .find_one(doc! { "artist_id": artist_id as i64 })
+            .await?;
+        Ok(artist)
+    }
+
+    /// Retrieves the artists linked to the given common artist.
+    #[allow(dead_code)]
+    pub async fn find_by_common_artist(
+        &self,
+        common_artist_id: u64,
+    ) -> DbResult<Vec<StoredArtist>> {
+        let find_options = FindOptions::builder().sort(doc! { "artist_id": 1 }).build();
+        let artists = self
+            .collection
+            .find(doc! { "common_artist_id": common_artist_id as i64 })
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(artists)
+    }
+
+    /// Retrieves the artists credited on the most songs.
+    #[allow(dead_code)]
+    pub async fn most_credited(&self, limit: i64) -> DbResult<Vec<StoredArtist>> {
+        let find_options = FindOptions::builder()
+            .sort(doc! { "song_count": -1, "artist_id": 1 })
+            .limit(limit)
+            .build();
+        let artists = self
+            .collection
+            .find(doc! {})
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(artists)
+    }
+
+    /// Resolves all stored artists into entities and replaces the stored
+    /// entities with the result.
+    ///
+    /// The entities are written to a staging collection that is then renamed
+    /// over `artist_entities`, so readers never see a partial resolution.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the resolved entities, ordered by ID.
+    pub async fn resolve_entities(&self) -> DbResult<Vec<ArtistEntity>> {
+        let artists: Vec<StoredArtist> = self.collection.find(doc! {}).await?.try_collect().await?;
+        let entities = artist_identity::resolve(&artists, DateTime::now());
+
+        let staging = self
+            .database
+            .collection::<ArtistEntity>(collections::ARTIST_ENTITIES_STAGING);
+        staging.drop().await?;
+        self.database
+            .create_collection(collections::ARTIST_ENTITIES_STAGING)
+            .await?;
+        indexes::create_declared(
+            &self.database,
+            collections::ARTIST_ENTITIES,
+            collections::ARTIST_ENTITIES_STAGING,
+        )
+        .await?;
+        if !entities.is_empty() {
+            staging.insert_many(&entities).await?;
+        }
+
+        let name = self.database.name();
+        self.database
+            .client()
+            .database("admin")
+            .run_command(doc! {
+                "renameCollection": format!("{name}.{}", collections::ARTIST_ENTITIES_STAGING),
+                "to": format!("{name}.{}", collections::ARTIST_ENTITIES),
+                "dropTarget": true,
+            })
+            .await?;
+        Ok(entities)
+    }
+
+    /// Retrieves the entities for manual review, least confident first: those
+    /// of more than one record whose confidence is at most `max_confidence`,
+    /// and those with a candidate link at most that confident.
+    pub async fn entities_for_review(
+        &self,
+        max_confidence: f64,
+        limit: i64,
+    ) -> DbResult<Vec<ArtistEntity>> {
+        let find_options = FindOptions::builder()
+            .sort(doc! { "confidence": 1, "song_count": -1 })
+            .limit(limit)
+            .build();
+        let entities = self
+            .entities
+            .find(doc! { "$or": [
+                {
+                    "members.1": { "$exists": true },
+                    "confidence": { "$lte": max_confidence },
+                },
+                { "candidates.confidence": { "$lte": max_confidence } },
+            ] })
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(entities)
+    }
+
+    /// Retrieves the entity an artist record was resolved into.
+    #[allow(dead_code)]
+    pub async fn entity_of(&self, artist_id: u64) -> DbResult<Option<ArtistEntity>> {
+        let entity = self
+            .entities
+            .find_one(doc! { "members.artist_id": artist_id as i64 })
+            .await?;
+        Ok(entity)
+    }
+}
+
+/// Builds the filter matching the listed songs crediting any of
+/// `artist_ids`, or every listed song if `artist_ids` is `None`.
+fn listed_songs_filter(artist_ids: Option<&[i64]>) -> Document {
+    let mut filter = credited_filter(artist_ids);
+    filter.insert("removed_at", Bson::Null);
+    filter
+}
+
+/// Builds the filter matching the songs or song credits of `artist_ids`.
+fn credited_filter(artist_ids: Option<&[i64]>) -> Document {
+    match artist_ids {
+        Some(artist_ids) => doc! { "artists.artist_id": { "$in": artist_ids } },
+        None => doc! {},
+    }
+}
+
+/// Builds the filter matching the artists among `artist_ids`, or among all
+/// artists if `artist_ids` is `None`, that are not in `listed_ids`.
+fn unlisted_artists_filter(artist_ids: Option<&[i64]>, listed_ids: Vec<Bson>) -> Document {
+    let mut artist_id = doc! { "$nin": listed_ids };
+    if let Some(artist_ids) = artist_ids {
+        artist_id.insert("$in", artist_ids);
+    }
+    doc! { "artist_id": artist_id }
+}
+
+/// Builds the aggregation that rebuilds the given artists, or every credited
+/// artist if `artist_ids` is `None`, from the listed songs.
+///
+/// Release dates that are not BSON dates are left out of the artist's range.
+fn rebuild_pipeline(artist_ids: Option<&[i64]>) -> Vec<Document> {
+    let street_date = doc! { "$cond": [
+        { "$eq": [{ "$type": "$street_date" }, "date"] },
+        "$street_date",
+        null,
+    ] };
+    vec![
+        doc! { "$match": listed_songs_filter(artist_ids) },
+        doc! { "$sort": { "last_seen_at": -1 } },
+        doc! { "$unwind": "$artists" },
+        doc! { "$match": credited_filter(artist_ids) },
+        doc! { "$group": {
+            "_id": "$artists.artist_id",
+            "profile": { "$first": "$artists" },
+            "song_count": { "$sum": 1 },
+            "first_release_date": { "$min": street_date.clone() },
+            "last_release_date": { "$max": street_date },
+            "first_seen_at": { "$min": "$first_seen_at" },
+            "last_seen_at": { "$max": "$last_seen_at" },
+        } },
+        doc! { "$replaceWith": { "$mergeObjects": [
+            "$profile",
+            {
+                "song_count": "$song_count",
+                "first_release_date": "$first_release_date",
+                "last_release_date": "$last_release_date",
+                "first_seen_at": "$first_seen_at",
+                "last_seen_at": "$last_seen_at",
+            },
+        ] } },
+        doc! { "$merge": {
+            "into": collections::ARTISTS,
+            "on": "artist_id",
+            "whenMatched": "merge",
+            "whenNotMatched": "insert",
+        } },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebuilds_only_count_listed_songs_of_the_given_artists() {
+        let pipeline = rebuild_pipeline(Some(&[7, 9]));
+
+        assert_eq!(
+            pipeline[0],
+            doc! { "$match": {
+                "artists.artist_id": { "$in": [7_i64, 9_i64] },
+                "removed_at": null,
+            } }
+        );
+        assert_eq!(
+            pipeline[3],
+            doc! { "$match": { "artists.artist_id": { "$in": [7_i64, 9_i64] } } }
+        );
+        assert_eq!(
+            rebuild_pipeline(None)[0],
+            doc! { "$match": { "removed_at": null } }
+        );
+    }
+
+    #[test]
+    fn release_dates_that_are_not_dates_are_ignored() {
+        let pipeline = rebuild_pipeline(None);
+        let group = pipeline[4].get_document("$group").unwrap();
+        let guarded = doc! { "$cond": [
+            { "$eq": [{ "$type": "$street_date" }, "date"] },
+            "$street_date",
+            null,
+        ] };
+
+        assert_eq!(
+            group.get_document("first_release_date").unwrap(),
+            &doc! { "$min": guarded.clone() }
+        );
+        assert_eq!(
+            group.get_document("last_release_date").unwrap(),
+            &doc! { "$max": guarded }
+        );
+    }
+
+    #[test]
+    fn artists_without_listed_songs_are_reset() {
+        let listed = vec![Bson::Int64(7)];
+
+        assert_eq!(
+            unlisted_artists_filter(Some(&[7, 9]), listed.clone()),
+            doc! { "artist_id": { "$nin": [7_i64], "$in": [7_i64, 9_i64] } }
+        );
+        assert_eq!(
+            unlisted_artists_filter(None, listed),
+            doc! { "artist_id": { "$nin": [7_i64] } }
+        );
+    }
+}
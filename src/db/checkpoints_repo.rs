@@ -0,0 +1,283 @@
+//! Contains the repository logic for the `ingestion_checkpoints` collection.
+//! A checkpoint records how far a crawl of one query has progressed, so that
+//! an interrupted crawl can resume instead of starting again from page 1.
+
+use super::{collections, DbResult};
+use mongodb::{
+    bson::{doc, DateTime},
+    Collection,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The largest share of the songs by which the API total may change before a
+/// checkpoint is discarded.
+///
+/// Songs are released and withdrawn while a crawl is interrupted, so the total
+/// rarely matches exactly. Beyond this, so many songs have moved between pages
+/// that resuming would skip too many of them.
+const MAX_TOTAL_DRIFT: f64 = 0.05;
+
+/// The progress of a crawl over a single query.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrawlCheckpoint {
+    /// The key identifying the crawled query.
+    #[serde(rename = "_id")]
+    pub query: String,
+    /// The total number of songs reported by the API when the crawl started.
+    pub total: u64,
+    /// The number of songs per page the crawl was planned with.
+    pub per_page: u32,
+    /// The number of pages the crawl was planned with.
+    pub total_pages: u32,
+    /// The pages whose songs have been flushed to the database.
+    pub completed_pages: Vec<u32>,
+    /// When the crawl was started.
+    pub started_at: DateTime,
+    /// When the last batch of songs was flushed.
+    pub last_flush_at: Option<DateTime>,
+    /// When the crawl completed, if it did.
+    pub finished_at: Option<DateTime>,
+}
+
+impl CrawlCheckpoint {
+    /// Returns `true` if the crawl is unfinished and every planned page has been saved.
+    pub fn is_complete(&self) -> bool {
+        self.finished_at.is_none()
+            && (1..=self.total_pages).all(|page| self.completed_pages.contains(&page))
+    }
+
+    /// Checks whether the checkpoint belongs to an unfinished crawl that can
+    /// continue with the current page size and total.
+    ///
+    /// # Returns
+    /// `Ok` if the crawl can resume, or the reason the checkpoint must be discarded.
+    pub fn can_resume(&self, total: u64, per_page: u32) -> Result<(), ResumeConflict> {
+        if self.finished_at.is_some() {
+            return Err(ResumeConflict::Finished);
+        }
+        if self.per_page != per_page {
+            return Err(ResumeConflict::PageSizeChanged {
+                was: self.per_page,
+                now: per_page,
+            });
+        }
+        if self.total.abs_diff(total) as f64 > self.total as f64 * MAX_TOTAL_DRIFT {
+            return Err(ResumeConflict::TotalDrifted {
+                was: self.total,
+                now: total,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The reason a checkpoint cannot be resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeConflict {
+    /// The crawl already completed.
+    Finished,
+    /// The API now returns a different number of songs per page.
+    PageSizeChanged {
+        /// The page size of the checkpoint.
+        was: u32,
+        /// The current page size.
+        now: u32,
+    },
+    /// The total number of songs changed by more than the allowed drift.
+    TotalDrifted {
+        /// The total of the checkpoint.
+        was: u64,
+        /// The current total.
+        now: u64,
+    },
+}
+
+impl fmt::Display for ResumeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResumeConflict::Finished => write!(f, "the crawl already finished"),
+            ResumeConflict::PageSizeChanged { was, now } => {
+                write!(f, "the page size changed from {was} to {now}")
+            }
+            ResumeConflict::TotalDrifted { was, now } => write!(
+                f,
+                "the total changed from {was} to {now}, more than {:.0}%",
+                MAX_TOTAL_DRIFT * 100.0
+            ),
+        }
+    }
+}
+
+/// A repository for handling database operations on the `ingestion_checkpoints` collection.
+#[derive(Clone, Debug)]
+pub struct CheckpointsRepo {
+    collection: Collection<CrawlCheckpoint>,
+}
+
+impl CheckpointsRepo {
+    /// Creates a new `CheckpointsRepo`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the `mongodb::Database` instance.
+    pub(super) fn new(db: &mongodb::Database) -> Self {
+        Self {
+            collection: db.collection(collections::INGESTION_CHECKPOINTS),
+        }
+    }
+
+    /// Retrieves the checkpoint for a query, if one exists.
+    pub async fn find(&self, query: &str) -> DbResult<Option<CrawlCheckpoint>> {
+        Ok(self.collection.find_one(doc! { "_id": query }).await?)
+    }
+
+    /// Starts a new checkpoint for a query, replacing any previous one.
+    ///
+    /// # Arguments
+    /// * `query` - The key identifying the crawled query.
+    /// * `total` - The total number of songs reported by the API.
+    /// * `per_page` - The number of songs per page.
+    /// * `total_pages` - The number of pages to crawl.
+    pub async fn start(
+        &self,
+        query: &str,
+        total: u64,
+        per_page: u32,
+        total_pages: u32,
+    ) -> DbResult<CrawlCheckpoint> {
+        let checkpoint = CrawlCheckpoint {
+            query: query.to_owned(),
+            total,
+            per_page,
+            total_pages,
+            completed_pages: Vec::new(),
+            started_at: DateTime::now(),
+            last_flush_at: None,
+            finished_at: None,
+        };
+
+        self.collection
+            .replace_one(doc! { "_id": query }, &checkpoint)
+            .upsert(true)
+            .await?;
+
+        Ok(checkpoint)
+    }
+
+    /// Records that the songs of `pages` have been flushed to the database.
+    pub async fn record_flush(&self, query: &str, pages: &[u32]) -> DbResult<()> {
+        if pages.is_empty() {
+            return Ok(());
+        }
+
+        let pages: Vec<i64> = pages.iter().map(|&page| page as i64).collect();
+        self.collection
+            .update_one(
+                doc! { "_id": query },
+                doc! {
+                    "$addToSet": { "completed_pages": { "$each": pages } },
+                    "$set": { "last_flush_at": DateTime::now() },
+                },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Updates the plan of a resumed crawl after the API total drifted.
+    pub async fn update_plan(&self, query: &str, total: u64, total_pages: u32) -> DbResult<()> {
+        self.collection
+            .update_one(
+                doc! { "_id": query },
+                doc! { "$set": { "total": total as i64, "total_pages": total_pages as i64 } },
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Marks the crawl of a query as completed.
+    pub async fn finish(&self, query: &str) -> DbResult<()> {
+        self.collection
+            .update_one(
+                doc! { "_id": query },
+                doc! { "$set": { "finished_at": DateTime::now() } },
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the checkpoint of a query, forcing the next crawl to start fresh.
+    ///
+    /// # Returns
+    /// A `DbResult` containing `true` if a checkpoint was deleted.
+    pub async fn delete(&self, query: &str) -> DbResult<bool> {
+        let result = self.collection.delete_one(doc! { "_id": query }).await?;
+        Ok(result.deleted_count > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(total: u64, per_page: u32) -> CrawlCheckpoint {
+        CrawlCheckpoint {
+            query: "songs".to_owned(),
+            total,
+            per_page,
+            total_pages: total.div_ceil(per_page as u64) as u32,
+            completed_pages: vec![1, 2],
+            started_at: DateTime::from_millis(0),
+            last_flush_at: None,
+            finished_at: None,
+        }
+    }
+
+    #[test]
+    fn same_plan_resumes() {
+        assert_eq!(checkpoint(1000, 50).can_resume(1000, 50), Ok(()));
+    }
+
+    #[test]
+    fn small_total_drift_resumes() {
+        assert_eq!(checkpoint(1000, 50).can_resume(1050, 50), Ok(()));
+        assert_eq!(checkpoint(1000, 50).can_resume(950, 50), Ok(()));
+    }
+
+    #[test]
+    fn large_total_drift_is_rejected() {
+        assert_eq!(
+            checkpoint(1000, 50).can_resume(1051, 50),
+            Err(ResumeConflict::TotalDrifted {
+                was: 1000,
+                now: 1051
+            })
+        );
+    }
+
+    #[test]
+    fn page_size_change_is_rejected() {
+        assert_eq!(
+            checkpoint(1000, 50).can_resume(1000, 20),
+            Err(ResumeConflict::PageSizeChanged { was: 50, now: 20 })
+        );
+    }
+
+    #[test]
+    fn crawl_is_complete_once_every_page_is_saved() {
+        let mut checkpoint = checkpoint(120, 50);
+        assert!(!checkpoint.is_complete());
+        checkpoint.completed_pages.push(3);
+        assert!(checkpoint.is_complete());
+    }
+
+    #[test]
+    fn finished_crawl_is_rejected() {
+        let mut checkpoint = checkpoint(1000, 50);
+        checkpoint.finished_at = Some(DateTime::from_millis(1));
+        assert_eq!(
+            checkpoint.can_resume(1000, 50),
+            Err(ResumeConflict::Finished)
+        );
+    }
+}
@@ -0,0 +1,283 @@
+//! Builds the artist collaboration graph from song credits and computes
+//! basic metrics on it.
+//!
+//! Artists are nodes, and two artists are joined by an edge whose weight is
+//! the number of stored songs crediting both of them.
+
+use serde::Serialize;
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
+use tunecore::models::Artist;
+
+/// An artist in the collaboration graph.
+#[derive(Serialize, Debug, Clone)]
+pub struct ArtistNode {
+    /// The ID of the artist.
+    pub artist_id: u64,
+    /// The Japanese name of the artist.
+    pub name: String,
+    /// The number of collaborations the artist is credited on.
+    pub songs: u64,
+    /// The number of distinct collaborators.
+    pub degree: usize,
+    /// The number of songs shared with collaborators, summed over all of them.
+    pub weighted_degree: u64,
+    /// The index of the connected component the artist belongs to.
+    pub component: usize,
+}
+
+/// A collaboration between two artists.
+#[derive(Serialize, Debug, Clone)]
+pub struct CollaborationEdge {
+    /// The ID of the artist with the lower ID.
+    pub source: u64,
+    /// The ID of the artist with the higher ID.
+    pub target: u64,
+    /// The number of songs crediting both artists.
+    pub weight: u64,
+}
+
+/// Summary metrics of the collaboration graph.
+#[derive(Serialize, Debug, Clone)]
+pub struct GraphMetrics {
+    /// The number of artists.
+    pub nodes: usize,
+    /// The number of collaborating pairs.
+    pub edges: usize,
+    /// The share of all possible pairs that collaborated.
+    pub density: f64,
+    /// The number of connected components.
+    pub components: usize,
+    /// The sizes of the connected components, largest first.
+    pub component_sizes: Vec<usize>,
+    /// The average number of collaborators per artist.
+    pub average_degree: f64,
+}
+
+/// The artist collaboration graph.
+#[derive(Serialize, Debug)]
+pub struct CollaborationGraph {
+    /// The artists, ordered by ID.
+    pub nodes: Vec<ArtistNode>,
+    /// The collaborations, ordered by artist IDs.
+    pub edges: Vec<CollaborationEdge>,
+    /// Summary metrics of the graph.
+    pub metrics: GraphMetrics,
+}
+
+impl CollaborationGraph {
+    /// Builds the graph from the credited artists of songs.
+    ///
+    /// # Arguments
+    /// * `credits` - The credited artists of each song.
+    /// * `min_weight` - The number of shared songs an edge needs to be kept.
+    pub(crate) fn build(credits: Vec<Vec<Artist>>, min_weight: u64) -> Self {
+        let mut names: BTreeMap<u64, String> = BTreeMap::new();
+        let mut songs: BTreeMap<u64, u64> = BTreeMap::new();
+        let mut weights: BTreeMap<(u64, u64), u64> = BTreeMap::new();
+        for artists in credits {
+            let ids: BTreeSet<u64> = artists.iter().map(|artist| artist.artist_id).collect();
+            if ids.len() < 2 {
+                continue;
+            }
+            for artist in artists {
+                names.entry(artist.artist_id).or_insert(artist.name.ja);
+            }
+            let ids: Vec<u64> = ids.into_iter().collect();
+            for (position, &source) in ids.iter().enumerate() {
+                *songs.entry(source).or_default() += 1;
+                for &target in &ids[position + 1..] {
+                    *weights.entry((source, target)).or_default() += 1;
+                }
+            }
+        }
+
+        let edges: Vec<CollaborationEdge> = weights
+            .into_iter()
+            .filter(|&(_, weight)| weight >= min_weight)
+            .map(|((source, target), weight)| CollaborationEdge {
+                source,
+                target,
+                weight,
+            })
+            .collect();
+        Self::from_edges(&names, &songs, edges)
+    }
+
+    /// Assembles the graph and its metrics from the kept edges.
+    fn from_edges(
+        names: &BTreeMap<u64, String>,
+        songs: &BTreeMap<u64, u64>,
+        edges: Vec<CollaborationEdge>,
+    ) -> Self {
+        let mut neighbours: BTreeMap<u64, Vec<(u64, u64)>> = BTreeMap::new();
+        for edge in &edges {
+            neighbours
+                .entry(edge.source)
+                .or_default()
+                .push((edge.target, edge.weight));
+            neighbours
+                .entry(edge.target)
+                .or_default()
+                .push((edge.source, edge.weight));
+        }
+
+        let mut components: BTreeMap<u64, usize> = BTreeMap::new();
+        let mut component_sizes = Vec::new();
+        for &start in neighbours.keys() {
+            if components.contains_key(&start) {
+                continue;
+            }
+            let component = component_sizes.len();
+            let mut size = 0;
+            let mut stack = vec![start];
+            components.insert(start, component);
+            while let Some(artist_id) = stack.pop() {
+                size += 1;
+                for &(neighbour, _) in &neighbours[&artist_id] {
+                    if let Entry::Vacant(entry) = components.entry(neighbour) {
+                        entry.insert(component);
+                        stack.push(neighbour);
+                    }
+                }
+            }
+            component_sizes.push(size);
+        }
+
+        let nodes: Vec<ArtistNode> = neighbours
+            .iter()
+            .map(|(&artist_id, adjacent)| ArtistNode {
+                artist_id,
+                name: names.get(&artist_id).cloned().unwrap_or_default(),
+                songs: songs.get(&artist_id).copied().unwrap_or_default(),
+                degree: adjacent.len(),
+                weighted_degree: adjacent.iter().map(|&(_, weight)| weight).sum(),
+                component: components[&artist_id],
+            })
+            .collect();
+
+        let node_count = nodes.len();
+        let possible_edges = node_count * node_count.saturating_sub(1) / 2;
+        component_sizes.sort_unstable_by(|a, b| b.cmp(a));
+        let metrics = GraphMetrics {
+            nodes: node_count,
+            edges: edges.len(),
+            density: if possible_edges == 0 {
+                0.0
+            } else {
+                edges.len() as f64 / possible_edges as f64
+            },
+            components: component_sizes.len(),
+            component_sizes,
+            average_degree: if node_count == 0 {
+                0.0
+            } else {
+                2.0 * edges.len() as f64 / node_count as f64
+            },
+        };
+
+        Self {
+            nodes,
+            edges,
+            metrics,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tunecore::models::ArtistName;
+
+    fn artist(artist_id: u64) -> Artist {
+        Artist {
+            artist_id,
+            name: ArtistName {
+                ja: format!("Artist {artist_id}"),
+                en: None,
+                ja_kana: None,
+            },
+            is_common_artist: false,
+            is_artist_page_available: false,
+            artist_page_path: String::new(),
+            common_artist_id: None,
+        }
+    }
+
+    fn credits(songs: &[&[u64]]) -> Vec<Vec<Artist>> {
+        songs
+            .iter()
+            .map(|ids| ids.iter().map(|&id| artist(id)).collect())
+            .collect()
+    }
+
+    fn edge(source: u64, target: u64, weight: u64) -> CollaborationEdge {
+        CollaborationEdge {
+            source,
+            target,
+            weight,
+        }
+    }
+
+    #[test]
+    fn metrics_describe_components_and_density() {
+        let names = BTreeMap::new();
+        let songs = BTreeMap::new();
+        let edges = vec![edge(1, 2, 3), edge(2, 3, 1), edge(4, 5, 2)];
+
+        let graph = CollaborationGraph::from_edges(&names, &songs, edges);
+
+        assert_eq!(graph.metrics.nodes, 5);
+        assert_eq!(graph.metrics.edges, 3);
+        assert_eq!(graph.metrics.components, 2);
+        assert_eq!(graph.metrics.component_sizes, vec![3, 2]);
+        assert!((graph.metrics.density - 0.3).abs() < 1e-9);
+        assert!((graph.metrics.average_degree - 1.2).abs() < 1e-9);
+
+        let node = &graph.nodes[1];
+        assert_eq!(node.artist_id, 2);
+        assert_eq!(node.degree, 2);
+        assert_eq!(node.weighted_degree, 4);
+        assert_eq!(node.component, graph.nodes[0].component);
+        assert_ne!(graph.nodes[3].component, node.component);
+    }
+
+    #[test]
+    fn an_empty_graph_has_zero_metrics() {
+        let graph = CollaborationGraph::from_edges(&BTreeMap::new(), &BTreeMap::new(), Vec::new());
+
+        assert_eq!(graph.metrics.nodes, 0);
+        assert_eq!(graph.metrics.density, 0.0);
+        assert_eq!(graph.metrics.average_degree, 0.0);
+        assert!(graph.metrics.component_sizes.is_empty());
+    }
+
+    #[test]
+    fn build_weighs_edges_by_shared_songs() {
+        let graph = CollaborationGraph::build(credits(&[&[1, 2], &[2, 1, 3], &[4]]), 1);
+
+        let edges: Vec<(u64, u64, u64)> = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.source, edge.target, edge.weight))
+            .collect();
+        assert_eq!(edges, vec![(1, 2, 2), (1, 3, 1), (2, 3, 1)]);
+        assert_eq!(graph.nodes[0].name, "Artist 1");
+        assert_eq!(graph.nodes[0].songs, 2);
+    }
+
+    #[test]
+    fn build_drops_light_edges_and_their_lone_artists() {
+        let graph = CollaborationGraph::build(credits(&[&[1, 2], &[1, 2], &[2, 3]]), 2);
+
+        assert_eq!(graph.metrics.edges, 1);
+        let ids: Vec<u64> = graph.nodes.iter().map(|node| node.artist_id).collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn artists_credited_twice_on_a_song_are_not_self_linked() {
+        let graph = CollaborationGraph::build(credits(&[&[1, 1]]), 1);
+
+        assert_eq!(graph.metrics.nodes, 0);
+    }
+}
@@ -2,3 +2,37 @@
 
 /// The name of the collection for community songs.
 pub const SONGS: &str = "songs";
+
+/// The name of the collection for crawl progress checkpoints.
+pub const INGESTION_CHECKPOINTS: &str = "ingestion_checkpoints";
+
+/// The name of the collection for pages that failed to be fetched.
+pub const FAILED_PAGES: &str = "failed_pages";
+
+/// The name of the collection for the history of ingestion runs.
+pub const INGESTION_RUNS: &str = "ingestion_runs";
+
+/// The name of the collection for previous versions of songs.
+pub const SONG_HISTORY: &str = "song_history";
+
+/// The name of the collection for the positions of songs in crawled queries.
+pub const SONG_POSITIONS: &str = "song_positions";
+
+/// The name of the time-series collection for popularity ranks.
+pub const POPULARITY_SNAPSHOTS: &str = "popularity_snapshots";
+
+/// The name of the collection for the history of share rates.
+pub const SHARE_RATES: &str = "share_rates";
+
+/// The name of the collection for artists credited on songs.
+pub const ARTISTS: &str = "artists";
+
+/// The name of the collection for artists resolved from several artist records.
+pub const ARTIST_ENTITIES: &str = "artist_entities";
+
+/// The name of the collection new artist entities are written to before they
+/// replace `artist_entities`.
+pub const ARTIST_ENTITIES_STAGING: &str = "artist_entities_staging";
+
+/// The name of the collection recording applied migrations and the migration lock.
+pub const MIGRATIONS: &str = "_migrations";
@@ -14,11 +14,19 @@ pub enum DbError {
     #[error("Bson error: {0}")]
     Bson(#[from] mongodb::bson::ser::Error),
 
-    /// Represents an error originating from the Tunecore client library.
-    #[error("Tunecore error: {0}")]
-    Tunecore(#[from] tunecore::error::Error),
+    /// Represents an error while decoding a BSON document.
+    #[error("Bson decode error: {0}")]
+    BsonDecode(#[from] mongodb::bson::de::Error),
 
-    /// Represents an error originating from the env error
-    #[error("Environment variable error: {0}")]
-    Env(#[from] std::env::VarError),
+    /// Represents an attempt to migrate while another process holds the lock.
+    #[error("Migration locked: {0}")]
+    MigrationLocked(String),
+
+    /// Represents a continuation token that could not be decoded.
+    #[error("Invalid cursor: {0}")]
+    InvalidCursor(String),
+
+    /// Represents an operation that needs migrations which have not been applied.
+    #[error("{0} pending migrations; run `extractor migrate` first")]
+    MigrationsPending(usize),
 }
@@ -0,0 +1,101 @@
+//! Contains the repository logic for the `failed_pages` collection.
+//! This acts as a dead-letter store for pages that could not be fetched
+//! during a crawl, so they can be retried later without re-crawling everything.
+
+use super::{collections, DbResult};
+use futures_util::stream::TryStreamExt;
+use mongodb::{
+    bson::{doc, DateTime},
+    options::FindOptions,
+    Collection,
+};
+use serde::{Deserialize, Serialize};
+
+/// A page that failed to be fetched.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FailedPage {
+    /// The key identifying the crawled query.
+    pub query: String,
+    /// The page number that failed.
+    pub page: u32,
+    /// The error message of the most recent failure.
+    pub error: String,
+    /// The number of times fetching this page has failed.
+    pub attempts: u32,
+    /// When the page first failed.
+    pub first_failed_at: DateTime,
+    /// When the page most recently failed.
+    pub last_failed_at: DateTime,
+}
+
+/// A repository for handling database operations on the `failed_pages` collection.
+#[derive(Clone, Debug)]
+pub struct FailedPagesRepo {
+    collection: Collection<FailedPage>,
+}
+
+impl FailedPagesRepo {
+    /// Creates a new `FailedPagesRepo`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the `mongodb::Database` instance.
+    pub(super) fn new(db: &mongodb::Database) -> Self {
+        Self {
+            collection: db.collection(collections::FAILED_PAGES),
+        }
+    }
+
+    /// Records a failed attempt to fetch a page, incrementing its attempt count.
+    ///
+    /// # Arguments
+    /// * `query` - The key identifying the crawled query.
+    /// * `page` - The page number that failed.
+    /// * `error` - A description of the failure.
+    pub async fn record_failure(&self, query: &str, page: u32, error: &str) -> DbResult<()> {
+        let now = DateTime::now();
+        self.collection
+            .update_one(
+                doc! { "query": query, "page": page as i64 },
+                doc! {
+                    "$set": { "error": error, "last_failed_at": now },
+                    "$inc": { "attempts": 1_i64 },
+                    "$setOnInsert": { "first_failed_at": now },
+                },
+            )
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    /// Retrieves all failed pages of a query, ordered by page number.
+    pub async fn list(&self, query: &str) -> DbResult<Vec<FailedPage>> {
+        let find_options = FindOptions::builder().sort(doc! { "page": 1 }).build();
+
+        let pages = self
+            .collection
+            .find(doc! { "query": query })
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(pages)
+    }
+
+    /// Removes pages of a query that have since been fetched successfully.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the number of entries removed.
+    pub async fn resolve(&self, query: &str, pages: &[u32]) -> DbResult<u64> {
+        if pages.is_empty() {
+            return Ok(0);
+        }
+
+        let pages: Vec<i64> = pages.iter().map(|&page| page as i64).collect();
+        let result = self
+            .collection
+            .delete_many(doc! { "query": query, "page": { "$in": pages } })
+            .await?;
+        Ok(result.deleted_count)
+    }
+}
@@ -0,0 +1,63 @@
+//! Computes content fingerprints of songs.
+//!
+//! A fingerprint is the SHA-256 hash of the BSON encoding of a song's content
+//! fields, so it only changes when the song itself changes, not when it moves
+//! to another position in a response.
+
+use super::songs_repo::CanonicalSong;
+use super::{song_history, DbResult};
+use mongodb::bson::{to_document, to_vec};
+use sha2::{Digest, Sha256};
+
+/// Returns the content hash of a song as a hex string.
+pub(super) fn content_hash(song: &CanonicalSong) -> DbResult<String> {
+    let content = song_history::content(&to_document(song)?);
+    let digest = Sha256::digest(to_vec(&content)?);
+    Ok(format!("{digest:x}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::songs_repo::tests::community_song;
+
+    #[test]
+    fn hash_is_stable() {
+        let song = CanonicalSong::try_from(&community_song(1, "歌")).unwrap();
+
+        assert_eq!(
+            content_hash(&song).unwrap(),
+            content_hash(&song.clone()).unwrap()
+        );
+        // Stored hashes must survive upgrades, or every song is rewritten.
+        assert_eq!(
+            content_hash(&song).unwrap(),
+            "24596b15aaad73afa1b7916855c84d067c3bbbff30dc97e7235422cfb65ba41d"
+        );
+    }
+
+    #[test]
+    fn hash_ignores_the_position_and_favorite_flag() {
+        let listed = community_song(1, "歌");
+        let mut moved = listed.clone();
+        moved.index = 17;
+        moved.is_favorite = true;
+
+        assert_eq!(
+            content_hash(&CanonicalSong::try_from(&listed).unwrap()).unwrap(),
+            content_hash(&CanonicalSong::try_from(&moved).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn hash_changes_with_the_content() {
+        let song = community_song(1, "歌");
+        let mut retitled = song.clone();
+        retitled.song_title.en = Some("Song".to_owned());
+
+        assert_ne!(
+            content_hash(&CanonicalSong::try_from(&song).unwrap()).unwrap(),
+            content_hash(&CanonicalSong::try_from(&retitled).unwrap()).unwrap()
+        );
+    }
+}
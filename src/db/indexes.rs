@@ -0,0 +1,443 @@
+//! Declares the indexes every collection needs and keeps the database in
+//! line with them. Missing indexes are created on startup; indexes that
+//! differ from their declaration or are not declared at all are reported
+//! as drift instead of being changed, since fixing them may need a rebuild.
+
+use super::{collections, DbResult};
+use futures_util::stream::TryStreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::IndexOptions,
+    Database, IndexModel,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+use tracing::warn;
+
+/// The index MongoDB creates on `_id` for every collection.
+const ID_INDEX: &str = "_id_";
+
+/// The declaration of an index.
+#[derive(Debug, Clone)]
+pub struct IndexSpec {
+    /// The collection the index belongs to.
+    pub collection: &'static str,
+    /// The name of the index.
+    pub name: &'static str,
+    /// The indexed fields and their directions.
+    pub keys: Document,
+    /// Whether the index rejects duplicate keys.
+    pub unique: bool,
+}
+
+impl IndexSpec {
+    /// Declares a non-unique index.
+    fn new(collection: &'static str, name: &'static str, keys: Document) -> Self {
+        Self {
+            collection,
+            name,
+            keys,
+            unique: false,
+        }
+    }
+
+    /// Declares a unique index.
+    fn unique(collection: &'static str, name: &'static str, keys: Document) -> Self {
+        Self {
+            unique: true,
+            ..Self::new(collection, name, keys)
+        }
+    }
+
+    /// Builds the model used to create the index.
+    fn to_model(&self) -> IndexModel {
+        let options = IndexOptions::builder()
+            .name(self.name.to_owned())
+            .unique(self.unique.then_some(true))
+            .build();
+        IndexModel::builder()
+            .keys(self.keys.clone())
+            .options(options)
+            .build()
+    }
+
+    /// Returns `true` if an existing index matches this declaration.
+    fn matches(&self, index: &IndexModel) -> bool {
+        let unique = index
+            .options
+            .as_ref()
+            .and_then(|options| options.unique)
+            .unwrap_or(false);
+        index.keys == self.keys && unique == self.unique
+    }
+}
+
+/// Returns the declarations of all indexes the application relies on.
+pub fn specs() -> Vec<IndexSpec> {
+    vec![
+        IndexSpec::unique(collections::SONGS, "id_unique", doc! { "id": 1 }),
+        IndexSpec::new(collections::SONGS, "genre_id", doc! { "genre_id": 1 }),
+        IndexSpec::new(collections::SONGS, "mood_id", doc! { "mood_id": 1 }),
+        IndexSpec::new(
+            collections::SONGS,
+            "street_date_id",
+            doc! { "street_date": 1, "id": 1 },
+        ),
+        IndexSpec::new(collections::SONGS, "bpm_id", doc! { "bpm": 1, "id": 1 }),
+        IndexSpec::new(
+            collections::SONGS,
+            "duration_id",
+            doc! { "duration": 1, "id": 1 },
+        ),
+        IndexSpec::new(
+            collections::SONGS,
+            "first_seen_at_id",
+            doc! { "first_seen_at": 1, "id": 1 },
+        ),
+        IndexSpec::new(
+            collections::SONGS,
+            "last_seen_at_id",
+            doc! { "last_seen_at": 1, "id": 1 },
+        ),
+        IndexSpec::new(
+            collections::SONGS,
+            "artists_artist_id",
+            doc! { "artists.artist_id": 1 },
+        ),
+        IndexSpec::unique(
+            collections::ARTISTS,
+            "artist_id_unique",
+            doc! { "artist_id": 1 },
+        ),
+        IndexSpec::new(
+            collections::ARTISTS,
+            "common_artist_id",
+            doc! { "common_artist_id": 1 },
+        ),
+        IndexSpec::new(
+            collections::ARTIST_ENTITIES,
+            "members_artist_id",
+            doc! { "members.artist_id": 1 },
+        ),
+        IndexSpec::new(
+            collections::ARTIST_ENTITIES,
+            "confidence",
+            doc! { "confidence": 1 },
+        ),
+        IndexSpec::new(
+            collections::SONG_HISTORY,
+            "song_id_changed_at",
+            doc! { "song_id": 1, "changed_at": 1 },
+        ),
+        IndexSpec::unique(
+            collections::SONG_POSITIONS,
+            "query_song_id",
+            doc! { "query": 1, "song_id": 1 },
+        ),
+        IndexSpec::unique(
+            collections::FAILED_PAGES,
+            "query_page",
+            doc! { "query": 1, "page": 1 },
+        ),
+        IndexSpec::new(
+            collections::SHARE_RATES,
+            "song_id_observed_at",
+            doc! { "song_id": 1, "observed_at": -1 },
+        ),
+        IndexSpec::new(collections::SHARE_RATES, "run_id", doc! { "run_id": 1 }),
+        IndexSpec::new(
+            collections::INGESTION_RUNS,
+            "started_at",
+            doc! { "started_at": -1 },
+        ),
+    ]
+}
+
+/// How an index in the database differs from the declarations.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftKind {
+    /// A declared index does not exist.
+    Missing,
+    /// An index exists under the declared name with other keys or options,
+    /// or with the declared keys under another name.
+    Mismatched,
+    /// An index exists that is not declared.
+    Unexpected,
+}
+
+/// A difference between the declared and the existing indexes.
+#[derive(Serialize, Debug, Clone)]
+pub struct IndexDrift {
+    /// The collection of the index.
+    pub collection: String,
+    /// The name of the index.
+    pub name: String,
+    /// How the index differs.
+    pub kind: DriftKind,
+    /// The declared keys, if the index is declared.
+    pub expected: Option<Document>,
+    /// The existing keys, if the index exists.
+    pub actual: Option<Document>,
+    /// The name of the existing index, if it differs from the declared one.
+    pub actual_name: Option<String>,
+}
+
+/// Compares the indexes in the database with the declarations.
+pub(super) async fn check(database: &Database) -> DbResult<Vec<IndexDrift>> {
+    let specs = specs();
+    let existing_collections: HashSet<String> = database
+        .list_collection_names()
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut checked = Vec::new();
+    let mut drift = Vec::new();
+    for spec in &specs {
+        if checked.contains(&spec.collection) {
+            continue;
+        }
+        checked.push(spec.collection);
+
+        let indexes: Vec<IndexModel> = if existing_collections.contains(spec.collection) {
+            database
+                .collection::<Document>(spec.collection)
+                .list_indexes()
+                .await?
+                .try_collect()
+                .await?
+        } else {
+            Vec::new()
+        };
+        let declared: Vec<&IndexSpec> = specs
+            .iter()
+            .filter(|other| other.collection == spec.collection)
+            .collect();
+        drift.extend(compare(spec.collection, &declared, &indexes));
+    }
+
+    Ok(drift)
+}
+
+/// Compares the declared indexes of one collection with its existing ones.
+///
+/// An existing index belongs to a declaration if it has the declared name
+/// or, failing that, the declared keys.
+fn compare(collection: &str, declared: &[&IndexSpec], indexes: &[IndexModel]) -> Vec<IndexDrift> {
+    let mut drift = Vec::new();
+    let mut claimed: Vec<&str> = vec![ID_INDEX];
+
+    for spec in declared {
+        let existing = indexes
+            .iter()
+            .find(|index| index_name(index) == Some(spec.name))
+            .or_else(|| {
+                indexes.iter().find(|index| {
+                    index.keys == spec.keys
+                        && !declared
+                            .iter()
+                            .any(|other| Some(other.name) == index_name(index))
+                })
+            });
+        let Some(index) = existing else {
+            drift.push(IndexDrift {
+                collection: collection.to_owned(),
+                name: spec.name.to_owned(),
+                kind: DriftKind::Missing,
+                expected: Some(spec.keys.clone()),
+                actual: None,
+                actual_name: None,
+            });
+            continue;
+        };
+
+        let name = index_name(index).unwrap_or_default();
+        claimed.push(name);
+        if name != spec.name || !spec.matches(index) {
+            drift.push(IndexDrift {
+                collection: collection.to_owned(),
+                name: spec.name.to_owned(),
+                kind: DriftKind::Mismatched,
+                expected: Some(spec.keys.clone()),
+                actual: Some(index.keys.clone()),
+                actual_name: (name != spec.name).then(|| name.to_owned()),
+            });
+        }
+    }
+
+    for index in indexes {
+        let name = index_name(index).unwrap_or_default();
+        if !claimed.contains(&name) {
+            drift.push(IndexDrift {
+                collection: collection.to_owned(),
+                name: name.to_owned(),
+                kind: DriftKind::Unexpected,
+                expected: None,
+                actual: Some(index.keys.clone()),
+                actual_name: None,
+            });
+        }
+    }
+
+    drift
+}
+
+/// Creates the declared indexes that are missing.
+///
+/// An index that cannot be created, for example because existing documents
+/// violate its uniqueness, is logged and stays in the drift as missing.
+///
+/// # Returns
+/// A `DbResult` containing the remaining drift, i.e. mismatched and
+/// unexpected indexes and those that could not be created, which are left
+/// for an operator to resolve.
+pub(super) async fn ensure(database: &Database) -> DbResult<Vec<IndexDrift>> {
+    let specs = specs();
+    let mut remaining = Vec::new();
+
+    for drift in check(database).await? {
+        if drift.kind != DriftKind::Missing {
+            remaining.push(drift);
+            continue;
+        }
+        let Some(spec) = specs
+            .iter()
+            .find(|spec| spec.collection == drift.collection && spec.name == drift.name)
+        else {
+            continue;
+        };
+        let created = database
+            .collection::<Document>(spec.collection)
+            .create_index(spec.to_model())
+            .await;
+        if let Err(error) = created {
+            warn!(
+                collection = spec.collection,
+                index = spec.name,
+                %error,
+                "Failed to create index."
+            );
+            remaining.push(drift);
+        }
+    }
+
+    Ok(remaining)
+}
+
+/// Creates the declared indexes of `collection` on `target`, a collection
+/// that is about to replace it.
+pub(super) async fn create_declared(
+    database: &Database,
+    collection: &str,
+    target: &str,
+) -> DbResult<()> {
+    let models: Vec<IndexModel> = specs()
+        .iter()
+        .filter(|spec| spec.collection == collection)
+        .map(IndexSpec::to_model)
+        .collect();
+    if !models.is_empty() {
+        database
+            .collection::<Document>(target)
+            .create_indexes(models)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Returns the name of an existing index.
+fn index_name(index: &IndexModel) -> Option<&str> {
+    index.options.as_ref()?.name.as_deref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn existing(name: &str, keys: Document, unique: bool) -> IndexModel {
+        let options = IndexOptions::builder()
+            .name(name.to_owned())
+            .unique(unique.then_some(true))
+            .build();
+        IndexModel::builder().keys(keys).options(options).build()
+    }
+
+    fn kinds(drift: &[IndexDrift]) -> Vec<(&str, DriftKind, Option<&str>)> {
+        drift
+            .iter()
+            .map(|drift| {
+                (
+                    drift.name.as_str(),
+                    drift.kind.clone(),
+                    drift.actual_name.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matching_indexes_have_no_drift() {
+        let spec = IndexSpec::unique("songs", "id_unique", doc! { "id": 1 });
+        let indexes = [
+            existing(ID_INDEX, doc! { "_id": 1 }, false),
+            existing("id_unique", doc! { "id": 1 }, true),
+        ];
+
+        assert!(compare("songs", &[&spec], &indexes).is_empty());
+    }
+
+    #[test]
+    fn other_keys_or_options_under_the_declared_name_are_mismatched() {
+        let by_keys = IndexSpec::new("songs", "bpm", doc! { "bpm": 1 });
+        let by_unique = IndexSpec::unique("songs", "id_unique", doc! { "id": 1 });
+        let indexes = [
+            existing("bpm", doc! { "bpm": -1 }, false),
+            existing("id_unique", doc! { "id": 1 }, false),
+        ];
+
+        assert_eq!(
+            kinds(&compare("songs", &[&by_keys, &by_unique], &indexes)),
+            vec![
+                ("bpm", DriftKind::Mismatched, None),
+                ("id_unique", DriftKind::Mismatched, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn declared_keys_under_another_name_are_mismatched_not_missing() {
+        let spec = IndexSpec::unique("songs", "id_unique", doc! { "id": 1 });
+        let indexes = [existing("id_1", doc! { "id": 1 }, true)];
+
+        assert_eq!(
+            kinds(&compare("songs", &[&spec], &indexes)),
+            vec![("id_unique", DriftKind::Mismatched, Some("id_1"))]
+        );
+    }
+
+    #[test]
+    fn an_index_named_for_another_declaration_is_not_matched_by_keys() {
+        let first = IndexSpec::new("songs", "first", doc! { "id": 1 });
+        let second = IndexSpec::new("songs", "second", doc! { "id": 1 });
+        let indexes = [existing("second", doc! { "id": 1 }, false)];
+
+        assert_eq!(
+            kinds(&compare("songs", &[&first, &second], &indexes)),
+            vec![("first", DriftKind::Missing, None)]
+        );
+    }
+
+    #[test]
+    fn undeclared_indexes_are_unexpected() {
+        let indexes = [
+            existing(ID_INDEX, doc! { "_id": 1 }, false),
+            existing("legacy", doc! { "index": 1 }, false),
+        ];
+
+        assert_eq!(
+            kinds(&compare("songs", &[], &indexes)),
+            vec![("legacy", DriftKind::Unexpected, None)]
+        );
+    }
+}
@@ -0,0 +1,33 @@
+//! Removes the per-request `index` and `is_favorite` fields from songs saved
+//! before songs were stored in their canonical form.
+
+use super::MigrationContext;
+use crate::db::{collections, DbResult};
+use futures::future::BoxFuture;
+use mongodb::{bson::doc, bson::Document};
+
+/// Applies the migration.
+pub(super) fn run<'a>(context: &'a MigrationContext<'a>) -> BoxFuture<'a, DbResult<u64>> {
+    Box::pin(async move {
+        let db = context.db;
+        let songs = db.collection::<Document>(collections::SONGS);
+        let filter = doc! {
+            "$or": [
+                { "index": { "$exists": true } },
+                { "is_favorite": { "$exists": true } },
+            ],
+        };
+
+        if context.dry_run {
+            return Ok(songs.count_documents(filter).await?);
+        }
+
+        let result = songs
+            .update_many(
+                filter,
+                doc! { "$unset": { "index": "", "is_favorite": "" } },
+            )
+            .await?;
+        Ok(result.modified_count)
+    })
+}
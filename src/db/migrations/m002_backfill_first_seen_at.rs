@@ -0,0 +1,27 @@
+//! Sets `first_seen_at` and `last_seen_at` on songs saved before lifecycle
+//! tracking, using the creation time encoded in their `_id`.
+
+use super::MigrationContext;
+use crate::db::{collections, DbResult};
+use futures::future::BoxFuture;
+use mongodb::{bson::doc, bson::Document};
+
+/// Applies the migration.
+pub(super) fn run<'a>(context: &'a MigrationContext<'a>) -> BoxFuture<'a, DbResult<u64>> {
+    Box::pin(async move {
+        let db = context.db;
+        let songs = db.collection::<Document>(collections::SONGS);
+        let filter = doc! { "first_seen_at": { "$exists": false } };
+
+        if context.dry_run {
+            return Ok(songs.count_documents(filter).await?);
+        }
+
+        let pipeline = vec![doc! { "$set": {
+            "first_seen_at": { "$toDate": "$_id" },
+            "last_seen_at": { "$ifNull": ["$last_seen_at", { "$toDate": "$_id" }] },
+        } }];
+        let result = songs.update_many(filter, pipeline).await?;
+        Ok(result.modified_count)
+    })
+}
@@ -0,0 +1,25 @@
+//! Builds the `artists` collection from the songs saved before artists were
+//! stored on their own.
+
+use super::MigrationContext;
+use crate::db::{collections, ArtistsRepo, DbResult};
+use futures::future::BoxFuture;
+use mongodb::{bson::doc, bson::Document};
+
+/// Applies the migration.
+pub(super) fn run<'a>(context: &'a MigrationContext<'a>) -> BoxFuture<'a, DbResult<u64>> {
+    Box::pin(async move {
+        let db = context.db;
+        let artist_ids = db
+            .collection::<Document>(collections::SONGS)
+            .distinct("artists.artist_id", doc! {})
+            .await?;
+
+        if !context.dry_run {
+            let artists = ArtistsRepo::new(db);
+            artists.ensure_indexes().await?;
+            artists.rebuild(None).await?;
+        }
+        Ok(artist_ids.len() as u64)
+    })
+}
@@ -0,0 +1,104 @@
+//! Converts `street_date` from a `YYYY-MM-DD` string to a BSON date in songs
+//! and in the previous versions and recorded changes of song history,
+//! recomputes the content hashes that depend on it, and rebuilds the
+//! artists' release dates.
+
+use super::MigrationContext;
+use crate::db::songs_repo::CanonicalSong;
+use crate::db::{collections, fingerprint, release_date, ArtistsRepo, DbResult};
+use futures::future::BoxFuture;
+use futures_util::stream::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_document, Document},
+    options::{UpdateModifications, UpdateOneModel, WriteModel},
+};
+
+/// The number of songs converted per bulk write.
+const BATCH_SIZE: usize = 1000;
+
+/// Applies the migration.
+pub(super) fn run<'a>(context: &'a MigrationContext<'a>) -> BoxFuture<'a, DbResult<u64>> {
+    Box::pin(async move {
+        let db = context.db;
+        let songs = db.collection::<Document>(collections::SONGS);
+        let history = db.collection::<Document>(collections::SONG_HISTORY);
+        let song_filter = doc! { "street_date": { "$type": "string" } };
+        let history_filter = doc! { "$or": [
+            { "previous.street_date": { "$type": "string" } },
+            { "changes": { "$elemMatch": {
+                "field": "street_date",
+                "$or": [{ "old": { "$type": "string" } }, { "new": { "$type": "string" } }],
+            } } },
+        ] };
+
+        if context.dry_run {
+            let songs = songs.count_documents(song_filter).await?;
+            let versions = history.count_documents(history_filter).await?;
+            return Ok(songs + versions);
+        }
+
+        let mut converted = 0;
+        let mut updates = Vec::with_capacity(BATCH_SIZE);
+        let mut cursor = songs.find(song_filter).await?;
+        while let Some(document) = cursor.try_next().await? {
+            let song: CanonicalSong = from_document(document)?;
+            let update = doc! { "$set": {
+                "street_date": release_date::to_datetime(&song.street_date),
+                "content_hash": fingerprint::content_hash(&song)?,
+            } };
+            let model = UpdateOneModel::builder()
+                .namespace(songs.namespace())
+                .filter(doc! { "id": song.id as i64 })
+                .update(UpdateModifications::Document(update))
+                .build();
+            updates.push(WriteModel::UpdateOne(model));
+
+            if updates.len() == BATCH_SIZE {
+                let result = db.client().bulk_write(updates.split_off(0)).await?;
+                converted += result.modified_count as u64;
+                context.keep_lock().await?;
+            }
+        }
+        if !updates.is_empty() {
+            let result = db.client().bulk_write(updates).await?;
+            converted += result.modified_count as u64;
+        }
+
+        let pipeline = vec![doc! { "$set": {
+            "previous.street_date": to_date("$previous.street_date"),
+            "changes": { "$map": {
+                "input": "$changes",
+                "as": "change",
+                "in": { "$cond": [
+                    { "$eq": ["$$change.field", "street_date"] },
+                    { "$mergeObjects": [
+                        "$$change",
+                        { "old": to_date("$$change.old"), "new": to_date("$$change.new") },
+                    ] },
+                    "$$change",
+                ] },
+            } },
+        } }];
+        let versions = history.update_many(history_filter, pipeline).await?;
+
+        let artists = ArtistsRepo::new(db);
+        artists.ensure_indexes().await?;
+        artists.rebuild(None).await?;
+        Ok(converted + versions.modified_count)
+    })
+}
+
+/// Builds the expression converting a `YYYY-MM-DD` string at `path` to a
+/// BSON date, leaving any other value as it is.
+fn to_date(path: &str) -> Document {
+    doc! { "$cond": [
+        { "$eq": [{ "$type": path }, "string"] },
+        { "$dateFromString": {
+            "dateString": path,
+            "format": "%Y-%m-%d",
+            "timezone": "UTC",
+            "onError": path,
+        } },
+        path,
+    ] }
+}
@@ -0,0 +1,363 @@
+//! A small framework for versioned data migrations.
+//!
+//! Migrations are declared in [`all`] as ordered, named steps. Applied
+//! migrations are recorded in the `_migrations` collection by version, so
+//! each runs exactly once per database. While migrations are applied, a lock
+//! document in the same collection keeps a second process from migrating
+//! concurrently. The holder renews the lock between migrations and batches;
+//! a lock not renewed for [`LOCK_TTL_MILLIS`] is considered abandoned and may
+//! be taken over.
+
+mod m001_strip_volatile_song_fields;
+mod m002_backfill_first_seen_at;
+mod m003_backfill_artists;
+mod m004_street_date_as_date;
+
+use super::{collections, DbError, DbResult};
+use futures::future::BoxFuture;
+use futures_util::stream::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime, Document},
+    error::{ErrorKind, WriteFailure},
+    options::FindOptions,
+    Collection, Database,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// The `_id` of the lock document in the `_migrations` collection.
+const LOCK_ID: &str = "lock";
+
+/// How long a lock is honored before it is considered abandoned.
+const LOCK_TTL_MILLIS: i64 = 60 * 60 * 1000;
+
+/// The server error code for duplicate keys.
+const DUPLICATE_KEY: i32 = 11000;
+
+/// The function applying a migration.
+///
+/// It returns the number of documents it changed or, in a dry run, would change.
+type MigrationFn = for<'a> fn(&'a MigrationContext<'a>) -> BoxFuture<'a, DbResult<u64>>;
+
+/// What a migration is applied with.
+pub(super) struct MigrationContext<'a> {
+    /// The database to migrate.
+    pub db: &'a Database,
+    /// Whether to only count the documents that would change.
+    pub dry_run: bool,
+    /// The migration lock, or `None` in a dry run.
+    lock: Option<&'a MigrationLock>,
+}
+
+impl MigrationContext<'_> {
+    /// Renews the migration lock, so that a long migration is not taken over
+    /// as abandoned. Migrations that write in batches call this between them.
+    pub async fn keep_lock(&self) -> DbResult<()> {
+        match self.lock {
+            Some(lock) => lock.renew().await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// The migration lock held by this process.
+struct MigrationLock {
+    locks: Collection<Document>,
+    holder: String,
+}
+
+impl MigrationLock {
+    /// Resets the time the lock was acquired to now.
+    ///
+    /// Fails with [`DbError::MigrationLocked`] if another process has taken
+    /// the lock over in the meantime.
+    async fn renew(&self) -> DbResult<()> {
+        let renewed = self
+            .locks
+            .update_one(
+                doc! { "_id": LOCK_ID, "holder": &self.holder },
+                doc! { "$set": { "acquired_at": DateTime::now() } },
+            )
+            .await?;
+        if renewed.matched_count == 0 {
+            return Err(lost_lock());
+        }
+        Ok(())
+    }
+
+    /// Releases the lock.
+    ///
+    /// Fails with [`DbError::MigrationLocked`] if another process has taken
+    /// the lock over in the meantime.
+    async fn release(self) -> DbResult<()> {
+        let released = self
+            .locks
+            .delete_one(doc! { "_id": LOCK_ID, "holder": &self.holder })
+            .await?;
+        if released.deleted_count == 0 {
+            return Err(lost_lock());
+        }
+        Ok(())
+    }
+}
+
+/// A single, versioned migration.
+pub struct Migration {
+    /// The version of the migration; migrations run in ascending order.
+    pub version: u32,
+    /// A short, unique name describing the migration.
+    pub name: &'static str,
+    /// Applies the migration.
+    run: MigrationFn,
+}
+
+/// Returns all migrations, in the order they are applied.
+fn all() -> Vec<Migration> {
+    let mut migrations = vec![
+        Migration {
+            version: 1,
+            name: "strip_volatile_song_fields",
+            run: m001_strip_volatile_song_fields::run,
+        },
+        Migration {
+            version: 2,
+            name: "backfill_first_seen_at",
+            run: m002_backfill_first_seen_at::run,
+        },
+        Migration {
+            version: 3,
+            name: "backfill_artists",
+            run: m003_backfill_artists::run,
+        },
+        Migration {
+            version: 4,
+            name: "street_date_as_date",
+            run: m004_street_date_as_date::run,
+        },
+    ];
+    migrations.sort_by_key(|migration| migration.version);
+    migrations
+}
+
+/// The record of an applied migration.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppliedMigration {
+    /// The version of the migration.
+    #[serde(rename = "_id")]
+    pub version: u32,
+    /// The name of the migration.
+    pub name: String,
+    /// When the migration finished.
+    pub applied_at: DateTime,
+    /// How long the migration took, in seconds.
+    pub duration_secs: f64,
+    /// The number of documents the migration changed.
+    pub documents: u64,
+}
+
+/// The state of a declared migration.
+#[derive(Serialize, Debug, Clone)]
+pub struct MigrationStatus {
+    /// The version of the migration.
+    pub version: u32,
+    /// The name of the migration.
+    pub name: String,
+    /// The record of the migration, if it was applied.
+    pub applied: Option<AppliedMigration>,
+}
+
+/// The outcome of running one migration.
+#[derive(Serialize, Debug, Clone)]
+pub struct MigrationOutcome {
+    /// The version of the migration.
+    pub version: u32,
+    /// The name of the migration.
+    pub name: String,
+    /// The number of documents changed, or that would be changed in a dry run.
+    pub documents: u64,
+    /// Whether the migration only reported what it would do.
+    pub dry_run: bool,
+}
+
+/// Runs migrations and reports their state.
+#[derive(Clone, Debug)]
+pub struct Migrator {
+    database: Database,
+    collection: Collection<AppliedMigration>,
+}
+
+impl Migrator {
+    /// Creates a new `Migrator`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the `mongodb::Database` instance.
+    pub(in crate::db) fn new(db: &Database) -> Self {
+        Self {
+            database: db.clone(),
+            collection: db.collection(collections::MIGRATIONS),
+        }
+    }
+
+    /// Returns the state of every declared migration, in order.
+    pub async fn status(&self) -> DbResult<Vec<MigrationStatus>> {
+        let mut applied = self.applied().await?;
+        Ok(all()
+            .into_iter()
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                name: migration.name.to_owned(),
+                applied: applied.remove(&migration.version),
+            })
+            .collect())
+    }
+
+    /// Returns the number of declared migrations that have not been applied.
+    pub async fn pending_count(&self) -> DbResult<usize> {
+        let applied = self.applied().await?;
+        Ok(all()
+            .iter()
+            .filter(|migration| !applied.contains_key(&migration.version))
+            .count())
+    }
+
+    /// Applies all pending migrations in order.
+    ///
+    /// With `dry_run`, the migrations only count the documents they would
+    /// change; nothing is written and no lock is taken.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the outcome of each pending migration.
+    pub async fn run(&self, dry_run: bool) -> DbResult<Vec<MigrationOutcome>> {
+        if dry_run {
+            return self.run_pending(None).await;
+        }
+
+        let lock = self.lock().await?;
+        let result = self.run_pending(Some(&lock)).await;
+        let released = lock.release().await;
+        let outcomes = result?;
+        released?;
+        Ok(outcomes)
+    }
+
+    /// Runs the pending migrations, recording each applied one.
+    ///
+    /// Without a lock, this is a dry run.
+    async fn run_pending(&self, lock: Option<&MigrationLock>) -> DbResult<Vec<MigrationOutcome>> {
+        let dry_run = lock.is_none();
+        let context = MigrationContext {
+            db: &self.database,
+            dry_run,
+            lock,
+        };
+        let applied = self.applied().await?;
+        let mut outcomes = Vec::new();
+
+        for migration in all() {
+            if applied.contains_key(&migration.version) {
+                continue;
+            }
+
+            info!(
+                version = migration.version,
+                name = migration.name,
+                dry_run,
+                "Running migration."
+            );
+            context.keep_lock().await?;
+            let start_time = Instant::now();
+            let documents = (migration.run)(&context).await?;
+
+            if !dry_run {
+                let record = AppliedMigration {
+                    version: migration.version,
+                    name: migration.name.to_owned(),
+                    applied_at: DateTime::now(),
+                    duration_secs: start_time.elapsed().as_secs_f64(),
+                    documents,
+                };
+                self.collection.insert_one(&record).await?;
+            }
+
+            outcomes.push(MigrationOutcome {
+                version: migration.version,
+                name: migration.name.to_owned(),
+                documents,
+                dry_run,
+            });
+        }
+
+        Ok(outcomes)
+    }
+
+    /// Retrieves the applied migrations, keyed by version.
+    async fn applied(&self) -> DbResult<HashMap<u32, AppliedMigration>> {
+        let find_options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+        let applied: Vec<AppliedMigration> = self
+            .collection
+            .find(doc! { "_id": { "$type": "number" } })
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(applied
+            .into_iter()
+            .map(|migration| (migration.version, migration))
+            .collect())
+    }
+
+    /// Takes the migration lock, or fails if another process holds it.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the lock held by this process.
+    async fn lock(&self) -> DbResult<MigrationLock> {
+        let locks = self.collection.clone_with_type::<Document>();
+        let holder = format!("{}-{}", std::process::id(), ObjectId::new().to_hex());
+        let now = DateTime::now();
+        let lock = doc! { "_id": LOCK_ID, "holder": &holder, "acquired_at": now };
+
+        match locks.insert_one(&lock).await {
+            Ok(_) => return Ok(MigrationLock { locks, holder }),
+            Err(error) if !is_duplicate_key(&error) => return Err(error.into()),
+            Err(_) => {}
+        }
+
+        let stale_before = DateTime::from_millis(now.timestamp_millis() - LOCK_TTL_MILLIS);
+        let taken_over = locks
+            .update_one(
+                doc! { "_id": LOCK_ID, "acquired_at": { "$lt": stale_before } },
+                doc! { "$set": { "holder": &holder, "acquired_at": now } },
+            )
+            .await?;
+        if taken_over.modified_count == 1 {
+            warn!("Took over an abandoned migration lock.");
+            return Ok(MigrationLock { locks, holder });
+        }
+
+        let current = locks.find_one(doc! { "_id": LOCK_ID }).await?;
+        let since = current
+            .as_ref()
+            .and_then(|lock| lock.get_datetime("acquired_at").ok())
+            .map(|acquired_at| acquired_at.to_string())
+            .unwrap_or_else(|| "an unknown time".to_owned());
+        Err(DbError::MigrationLocked(format!(
+            "another process has been migrating since {since}"
+        )))
+    }
+}
+
+/// The error for a lock that another process took over.
+fn lost_lock() -> DbError {
+    DbError::MigrationLocked("another process took over the lock while migrating".to_owned())
+}
+
+/// Returns `true` if a write failed because of a duplicate key.
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) if write_error.code == DUPLICATE_KEY
+    )
+}
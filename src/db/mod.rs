@@ -1,13 +1,41 @@
 //! The main database module, acting as a connection manager and repository factory.
 
+mod artist_identity;
+mod artists_repo;
+mod checkpoints_repo;
+mod collaboration_graph;
 mod collections;
 mod error;
+mod failed_pages_repo;
+mod fingerprint;
+mod indexes;
+mod migrations;
+mod popularity_repo;
+mod positions_repo;
+pub mod release_date;
+mod runs_repo;
+mod share_rates_repo;
+mod song_filter;
+mod song_history;
 mod songs_repo;
 
+pub use artist_identity::{ArtistEntity, ArtistLink, MatchReason};
+pub use artists_repo::ArtistsRepo;
+pub use checkpoints_repo::{CheckpointsRepo, CrawlCheckpoint};
+pub use collaboration_graph::{ArtistNode, CollaborationGraph};
 pub use error::DbError;
-pub use songs_repo::SongsRepo;
+pub use failed_pages_repo::FailedPagesRepo;
+pub use indexes::{DriftKind, IndexDrift};
+pub use migrations::{MigrationOutcome, MigrationStatus, Migrator};
+pub use popularity_repo::PopularityRepo;
+pub use positions_repo::{PositionsRepo, SongPosition};
+pub use runs_repo::{IngestionRun, RunStats, RunStatus, RunsRepo};
+pub use share_rates_repo::{ShareRateChange, ShareRatesRepo};
+pub use song_filter::{Bounds, SongCursor, SongFilter, SongQueryOptions, SongSort, SongSortField};
+pub use songs_repo::{CalendarPeriod, CanonicalSong, ReleaseCount, SaveStats, SongsRepo};
 
 use mongodb::{Client, Database};
+use tracing::{info, warn};
 
 /// The specialized `Result` type for database operations.
 pub type DbResult<T> = Result<T, DbError>;
@@ -33,8 +61,84 @@ impl Db {
         Ok(Self { database })
     }
 
+    /// Prepares the database schema for use.
+    ///
+    /// This creates the declared indexes that do not exist yet and the
+    /// `popularity_snapshots` time-series collection. Indexes that differ from
+    /// their declaration are logged and returned, but never changed.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the index drift that remains.
+    pub async fn init(&self) -> DbResult<Vec<IndexDrift>> {
+        self.popularity().ensure_collection().await?;
+
+        let drift = indexes::ensure(&self.database).await?;
+        for drift in &drift {
+            warn!(
+                collection = drift.collection,
+                index = drift.name,
+                kind = ?drift.kind,
+                "Index drift detected."
+            );
+        }
+        let pending = self.migrations().pending_count().await?;
+        if pending > 0 {
+            warn!(
+                pending,
+                "Database has pending migrations. Run `extractor migrate` to apply them."
+            );
+        }
+        info!(drift = drift.len(), "Database schema is ready.");
+        Ok(drift)
+    }
+
+    /// Returns the runner for the versioned data migrations.
+    pub fn migrations(&self) -> Migrator {
+        Migrator::new(&self.database)
+    }
+
+    /// Compares the existing indexes with the declared ones without changing anything.
+    pub async fn index_drift(&self) -> DbResult<Vec<IndexDrift>> {
+        indexes::check(&self.database).await
+    }
+
     /// Returns a repository for interacting with the `songs` collection.
     pub fn songs(&self) -> SongsRepo {
         SongsRepo::new(&self.database)
     }
+
+    /// Returns a repository for interacting with the `artists` collection.
+    pub fn artists(&self) -> ArtistsRepo {
+        ArtistsRepo::new(&self.database)
+    }
+
+    /// Returns a repository for interacting with the `ingestion_checkpoints` collection.
+    pub fn checkpoints(&self) -> CheckpointsRepo {
+        CheckpointsRepo::new(&self.database)
+    }
+
+    /// Returns a repository for interacting with the `failed_pages` collection.
+    pub fn failed_pages(&self) -> FailedPagesRepo {
+        FailedPagesRepo::new(&self.database)
+    }
+
+    /// Returns a repository for interacting with the `song_positions` collection.
+    pub fn positions(&self) -> PositionsRepo {
+        PositionsRepo::new(&self.database)
+    }
+
+    /// Returns a repository for interacting with the `popularity_snapshots` collection.
+    pub fn popularity(&self) -> PopularityRepo {
+        PopularityRepo::new(&self.database)
+    }
+
+    /// Returns a repository for interacting with the `ingestion_runs` collection.
+    pub fn runs(&self) -> RunsRepo {
+        RunsRepo::new(&self.database)
+    }
+
+    /// Returns a repository for interacting with the `share_rates` collection.
+    pub fn share_rates(&self) -> ShareRatesRepo {
+        ShareRatesRepo::new(&self.database)
+    }
 }
@@ -0,0 +1,238 @@
+//! Contains the repository logic for the `popularity_snapshots` collection.
+//! Every crawl sorted by popularity records the rank of each song as of the
+//! time the crawl started, which turns the single popularity ordering the
+//! API exposes into a time series.
+
+use super::{collections, DbResult, SongPosition};
+use futures_util::stream::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    options::{FindOptions, TimeseriesGranularity, TimeseriesOptions},
+    Collection, Database,
+};
+use serde::{Deserialize, Serialize};
+
+/// The number of milliseconds in a day.
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// The rank of a song in one popularity snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PopularitySnapshot {
+    /// When the crawl that produced the snapshot started.
+    pub taken_at: DateTime,
+    /// The ID of the song.
+    pub song_id: u64,
+    /// The 1-based rank of the song by popularity.
+    pub rank: u64,
+    /// The ingestion run that observed the rank.
+    pub run_id: ObjectId,
+}
+
+/// How the rank of a song changed between two snapshots.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RankMove {
+    /// The ID of the song.
+    pub song_id: u64,
+    /// The rank in the earlier snapshot.
+    pub from_rank: u64,
+    /// The rank in the later snapshot.
+    pub to_rank: u64,
+    /// How many places the song climbed; negative if it fell.
+    pub change: i64,
+}
+
+/// A repository for handling database operations on the `popularity_snapshots` collection.
+#[derive(Clone, Debug)]
+pub struct PopularityRepo {
+    database: Database,
+    collection: Collection<PopularitySnapshot>,
+}
+
+impl PopularityRepo {
+    /// Creates a new `PopularityRepo`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the `mongodb::Database` instance.
+    pub(super) fn new(db: &Database) -> Self {
+        Self {
+            database: db.clone(),
+            collection: db.collection(collections::POPULARITY_SNAPSHOTS),
+        }
+    }
+
+    /// Creates the collection as a time-series collection if it does not exist yet.
+    ///
+    /// Time-series collections cannot be created implicitly by the first
+    /// insert, so [`Db::init`] runs this before the first snapshot is recorded.
+    ///
+    /// [`Db::init`]: super::Db::init
+    pub async fn ensure_collection(&self) -> DbResult<()> {
+        let existing = self
+            .database
+            .list_collection_names()
+            .filter(doc! { "name": collections::POPULARITY_SNAPSHOTS })
+            .await?;
+        if !existing.is_empty() {
+            return Ok(());
+        }
+
+        let timeseries = TimeseriesOptions::builder()
+            .time_field("taken_at".to_owned())
+            .meta_field(Some("song_id".to_owned()))
+            .granularity(Some(TimeseriesGranularity::Hours))
+            .build();
+        self.database
+            .create_collection(collections::POPULARITY_SNAPSHOTS)
+            .timeseries(timeseries)
+            .await?;
+        Ok(())
+    }
+
+    /// Records the ranks of `positions` in the snapshot taken at `taken_at`.
+    ///
+    /// Positions without a known rank are skipped.
+    pub async fn record(&self, taken_at: DateTime, positions: &[SongPosition]) -> DbResult<()> {
+        let snapshots: Vec<PopularitySnapshot> = positions
+            .iter()
+            .filter_map(|position| {
+                position.rank.map(|rank| PopularitySnapshot {
+                    taken_at,
+                    song_id: position.song_id,
+                    rank,
+                    run_id: position.run_id,
+                })
+            })
+            .collect();
+        if snapshots.is_empty() {
+            return Ok(());
+        }
+
+        self.collection
+            .insert_many(&snapshots)
+            .ordered(false)
+            .await?;
+        Ok(())
+    }
+
+    /// Retrieves the times of all snapshots, newest first.
+    #[allow(dead_code)]
+    pub async fn snapshot_times(&self) -> DbResult<Vec<DateTime>> {
+        let mut times: Vec<DateTime> = self
+            .collection
+            .distinct("taken_at", doc! {})
+            .await?
+            .into_iter()
+            .filter_map(|value| value.as_datetime().copied())
+            .collect();
+        times.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(times)
+    }
+
+    /// Retrieves the rank of a song in every snapshot, oldest first.
+    ///
+    /// # Arguments
+    /// * `song_id` - The ID of the song.
+    #[allow(dead_code)]
+    pub async fn rank_history(&self, song_id: u64) -> DbResult<Vec<PopularitySnapshot>> {
+        let find_options = FindOptions::builder().sort(doc! { "taken_at": 1 }).build();
+
+        let snapshots = self
+            .collection
+            .find(doc! { "song_id": song_id as i64 })
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(snapshots)
+    }
+
+    /// Retrieves the songs whose rank changed the most between two snapshots.
+    ///
+    /// Only songs ranked in both snapshots are compared.
+    ///
+    /// # Arguments
+    /// * `from` - The time of the earlier snapshot.
+    /// * `to` - The time of the later snapshot.
+    /// * `limit` - The maximum number of songs to retrieve.
+    #[allow(dead_code)]
+    pub async fn biggest_movers(
+        &self,
+        from: DateTime,
+        to: DateTime,
+        limit: i64,
+    ) -> DbResult<Vec<RankMove>> {
+        let rank_at = |time: DateTime| {
+            doc! { "$max": { "$cond": [{ "$eq": ["$taken_at", time] }, "$rank", null] } }
+        };
+        let pipeline = vec![
+            doc! { "$match": { "taken_at": { "$in": [from, to] } } },
+            doc! { "$group": {
+                "_id": "$song_id",
+                "from_rank": rank_at(from),
+                "to_rank": rank_at(to),
+            } },
+            doc! { "$match": { "from_rank": { "$ne": null }, "to_rank": { "$ne": null } } },
+            doc! { "$project": {
+                "_id": 0,
+                "song_id": "$_id",
+                "from_rank": 1,
+                "to_rank": 1,
+                "change": { "$subtract": ["$from_rank", "$to_rank"] },
+            } },
+            doc! { "$addFields": { "distance": { "$abs": "$change" } } },
+            doc! { "$sort": { "distance": -1, "song_id": 1 } },
+            doc! { "$limit": limit },
+        ];
+
+        let moves = self
+            .collection
+            .aggregate(pipeline)
+            .with_type::<RankMove>()
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(moves)
+    }
+
+    /// Retrieves the top songs of the last snapshot taken on the UTC day of `day`.
+    ///
+    /// # Arguments
+    /// * `day` - Any time on the day of interest.
+    /// * `limit` - The number of songs to retrieve.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the songs in rank order, or an empty list if
+    /// no snapshot was taken that day.
+    #[allow(dead_code)]
+    pub async fn top_on(&self, day: DateTime, limit: i64) -> DbResult<Vec<PopularitySnapshot>> {
+        let start = day.timestamp_millis().div_euclid(DAY_MILLIS) * DAY_MILLIS;
+        let start = DateTime::from_millis(start);
+        let end = DateTime::from_millis(start.timestamp_millis() + DAY_MILLIS);
+
+        let Some(latest) = self
+            .collection
+            .find_one(doc! { "taken_at": { "$gte": start, "$lt": end } })
+            .sort(doc! { "taken_at": -1 })
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let find_options = FindOptions::builder()
+            .sort(doc! { "rank": 1 })
+            .limit(limit)
+            .build();
+
+        let snapshots = self
+            .collection
+            .find(doc! { "taken_at": latest.taken_at })
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(snapshots)
+    }
+}
@@ -0,0 +1,89 @@
+//! Contains the repository logic for the `song_positions` collection.
+//! A song's position only means something for the query and sort order that
+//! produced it, so positions are kept here, keyed by query and song, instead
+//! of on the song document.
+
+use super::{collections, DbResult};
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    options::{UpdateModifications, UpdateOneModel, WriteModel},
+    Collection, Database,
+};
+use serde::{Deserialize, Serialize};
+
+/// The latest position of a song in the results of a query.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SongPosition {
+    /// The key identifying the crawled query, including its sort order.
+    pub query: String,
+    /// The ID of the song.
+    pub song_id: u64,
+    /// The page the song was returned on.
+    pub page: u32,
+    /// The `index` the API reported for the song on that page.
+    pub index: usize,
+    /// The 1-based position of the song across all pages, if the page size was known.
+    pub rank: Option<u64>,
+    /// The ingestion run that observed the position.
+    pub run_id: ObjectId,
+    /// When the position was observed.
+    pub observed_at: DateTime,
+}
+
+/// A repository for handling database operations on the `song_positions` collection.
+#[derive(Clone, Debug)]
+pub struct PositionsRepo {
+    database: Database,
+    collection: Collection<SongPosition>,
+}
+
+impl PositionsRepo {
+    /// Creates a new `PositionsRepo`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the `mongodb::Database` instance.
+    pub(super) fn new(db: &Database) -> Self {
+        Self {
+            database: db.clone(),
+            collection: db.collection(collections::SONG_POSITIONS),
+        }
+    }
+
+    /// Stores the given positions, replacing the previous position of each
+    /// song in the same query.
+    pub async fn record(&self, positions: &[SongPosition]) -> DbResult<()> {
+        if positions.is_empty() {
+            return Ok(());
+        }
+
+        let upserts = positions
+            .iter()
+            .map(|position| {
+                let filter = doc! {
+                    "query": &position.query,
+                    "song_id": position.song_id as i64,
+                };
+                let update = UpdateModifications::Document(doc! {
+                    "$set": mongodb::bson::to_document(position)?,
+                });
+
+                let model = UpdateOneModel::builder()
+                    .namespace(self.collection.namespace())
+                    .filter(filter)
+                    .update(update)
+                    .upsert(true)
+                    .build();
+
+                Ok(WriteModel::UpdateOne(model))
+            })
+            .collect::<DbResult<Vec<_>>>()?;
+
+        self.database
+            .client()
+            .bulk_write(upserts)
+            .ordered(false)
+            .await?;
+
+        Ok(())
+    }
+}
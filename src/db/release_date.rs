@@ -0,0 +1,174 @@
+//! Stores release dates as BSON dates.
+//!
+//! `CommunitySong::street_date` is a calendar date without a time zone. It is
+//! persisted as a BSON date at midnight UTC so that range queries compare
+//! dates rather than strings. Documents written before the `street_date_as_date`
+//! migration hold `YYYY-MM-DD` strings instead, so both forms are read.
+//!
+//! Use with `#[serde(with = "release_date")]`, or with
+//! `#[serde(with = "release_date::option")]` for optional dates.
+
+use chrono::TimeDelta;
+use mongodb::bson::{Bson, DateTime};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use tunecore::models::ReleaseDate;
+
+/// The number of milliseconds in a day.
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// Returns the date BSON dates count from.
+fn unix_epoch() -> ReleaseDate {
+    ReleaseDate::from_ymd_opt(1970, 1, 1).expect("1970-01-01 is a valid date")
+}
+
+/// Returns the BSON date at midnight UTC of a release date.
+pub fn to_datetime(date: &ReleaseDate) -> DateTime {
+    DateTime::from_millis(date.signed_duration_since(unix_epoch()).num_days() * DAY_MILLIS)
+}
+
+/// Returns the UTC calendar date of a BSON date.
+pub fn from_datetime(datetime: DateTime) -> Option<ReleaseDate> {
+    let days = datetime.timestamp_millis().div_euclid(DAY_MILLIS);
+    unix_epoch().checked_add_signed(TimeDelta::try_days(days)?)
+}
+
+/// Returns the current UTC calendar date.
+pub fn today() -> ReleaseDate {
+    from_datetime(DateTime::now()).expect("the current date is representable")
+}
+
+/// Reads a release date stored as a BSON date or a `YYYY-MM-DD` string.
+pub fn from_bson(value: &Bson) -> Option<ReleaseDate> {
+    match value {
+        Bson::DateTime(datetime) => from_datetime(*datetime),
+        Bson::String(date) => date.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Serializes a release date as a BSON date.
+pub fn serialize<S: Serializer>(date: &ReleaseDate, serializer: S) -> Result<S::Ok, S::Error> {
+    to_datetime(date).serialize(serializer)
+}
+
+/// Deserializes a release date stored as a BSON date or a `YYYY-MM-DD` string.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ReleaseDate, D::Error> {
+    let value = Bson::deserialize(deserializer)?;
+    from_bson(&value).ok_or_else(|| D::Error::custom(format!("invalid release date: {value}")))
+}
+
+/// Serde functions for optional release dates.
+pub mod option {
+    use super::*;
+
+    /// Serializes an optional release date as a BSON date or null.
+    pub fn serialize<S: Serializer>(
+        date: &Option<ReleaseDate>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        date.as_ref().map(to_datetime).serialize(serializer)
+    }
+
+    /// Deserializes an optional release date stored as a BSON date, a
+    /// `YYYY-MM-DD` string or null.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<ReleaseDate>, D::Error> {
+        match Bson::deserialize(deserializer)? {
+            Bson::Null => Ok(None),
+            value => from_bson(&value)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("invalid release date: {value}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{doc, from_document, to_document};
+    use serde::Deserialize;
+
+    fn date(year: i32, month: u32, day: u32) -> ReleaseDate {
+        ReleaseDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Release {
+        #[serde(with = "super")]
+        date: ReleaseDate,
+        #[serde(with = "super::option")]
+        planned: Option<ReleaseDate>,
+    }
+
+    #[test]
+    fn dates_are_stored_at_midnight_utc() {
+        assert_eq!(to_datetime(&date(1970, 1, 1)).timestamp_millis(), 0);
+        assert_eq!(
+            to_datetime(&date(2024, 3, 1)).timestamp_millis(),
+            1_709_251_200_000
+        );
+        assert_eq!(
+            to_datetime(&date(1969, 12, 31)).timestamp_millis(),
+            -DAY_MILLIS
+        );
+    }
+
+    #[test]
+    fn datetimes_are_read_as_their_utc_date() {
+        let noon = DateTime::from_millis(1_709_251_200_000 + DAY_MILLIS / 2);
+        let before_epoch = DateTime::from_millis(-1);
+
+        assert_eq!(from_datetime(noon), Some(date(2024, 3, 1)));
+        assert_eq!(from_datetime(before_epoch), Some(date(1969, 12, 31)));
+        assert_eq!(from_datetime(DateTime::MAX), None);
+    }
+
+    #[test]
+    fn dates_round_trip() {
+        for day in [
+            date(1, 1, 1),
+            date(1970, 1, 1),
+            date(2024, 2, 29),
+            date(9999, 12, 31),
+        ] {
+            assert_eq!(from_datetime(to_datetime(&day)), Some(day));
+        }
+    }
+
+    #[test]
+    fn both_stored_forms_are_read() {
+        let stored = Bson::DateTime(to_datetime(&date(2024, 3, 1)));
+
+        assert_eq!(from_bson(&stored), Some(date(2024, 3, 1)));
+        assert_eq!(from_bson(&Bson::from("2024-03-01")), Some(date(2024, 3, 1)));
+        assert_eq!(from_bson(&Bson::from("2024-02-30")), None);
+        assert_eq!(from_bson(&Bson::Int32(20240301)), None);
+    }
+
+    #[test]
+    fn serde_stores_dates_and_reads_both_forms() {
+        let release = Release {
+            date: date(2024, 3, 1),
+            planned: None,
+        };
+        let stored = to_document(&release).unwrap();
+        let midnight = Bson::DateTime(to_datetime(&date(2024, 3, 1)));
+
+        assert_eq!(stored, doc! { "date": midnight, "planned": null });
+        assert_eq!(from_document::<Release>(stored).unwrap(), release);
+
+        let legacy = doc! { "date": "2024-03-01", "planned": "2024-04-01" };
+        let read = from_document::<Release>(legacy).unwrap();
+        assert_eq!(read.date, date(2024, 3, 1));
+        assert_eq!(read.planned, Some(date(2024, 4, 1)));
+    }
+
+    #[test]
+    fn malformed_dates_are_rejected() {
+        assert!(from_document::<Release>(doc! { "date": "TBA", "planned": null }).is_err());
+        assert!(
+            from_document::<Release>(doc! { "date": "2024-03-01", "planned": "soon" }).is_err()
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Contains the repository logic for the `ingestion_runs` collection.
+//! Every crawl writes one document here, so there is a history of when
+//! crawls ran and what they did.
+
+use super::{collections, DbResult};
+use futures_util::stream::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    options::FindOptions,
+    Collection,
+};
+use serde::{Deserialize, Serialize};
+
+/// The state of an ingestion run.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    /// The run has started and not finished yet (or died without finishing).
+    Running,
+    /// The run finished and every page was fetched.
+    Completed,
+    /// The run finished, but some pages failed.
+    CompletedWithErrors,
+    /// The run was aborted by an error.
+    Failed,
+}
+
+/// The statistics of a finished run.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RunStats {
+    /// The total number of songs reported by the API, if the first page was fetched.
+    pub api_total: Option<u64>,
+    /// The number of pages fetched and saved successfully.
+    pub pages_fetched: u64,
+    /// The number of pages that failed.
+    pub pages_failed: u64,
+    /// The number of songs inserted for the first time.
+    pub songs_inserted: u64,
+    /// The number of existing songs whose stored document changed.
+    pub songs_updated: u64,
+    /// The number of existing songs that were left unchanged.
+    pub songs_unchanged: u64,
+    /// The number of stored songs the run found to be removed from the catalog.
+    #[serde(default)]
+    pub songs_removed: u64,
+    /// The errors encountered during the run.
+    pub errors: Vec<String>,
+}
+
+/// A single run of the ingestion pipeline.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IngestionRun {
+    /// The unique ID of the run.
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// What the run did, e.g. `collect` or `retry_failed`.
+    pub mode: String,
+    /// The key identifying the crawled query.
+    pub query: String,
+    /// The state of the run.
+    pub status: RunStatus,
+    /// When the run started.
+    pub started_at: DateTime,
+    /// When the run finished, if it did.
+    pub finished_at: Option<DateTime>,
+    /// How long the run took, in seconds.
+    pub duration_secs: Option<f64>,
+    /// What the run did.
+    #[serde(flatten)]
+    pub stats: RunStats,
+}
+
+/// A repository for handling database operations on the `ingestion_runs` collection.
+#[derive(Clone, Debug)]
+pub struct RunsRepo {
+    collection: Collection<IngestionRun>,
+}
+
+impl RunsRepo {
+    /// Creates a new `RunsRepo`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the `mongodb::Database` instance.
+    pub(super) fn new(db: &mongodb::Database) -> Self {
+        Self {
+            collection: db.collection(collections::INGESTION_RUNS),
+        }
+    }
+
+    /// Records the start of a new run.
+    ///
+    /// # Arguments
+    /// * `mode` - What the run does, e.g. `collect`.
+    /// * `query` - The key identifying the crawled query.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the ID of the new run.
+    pub async fn start(&self, mode: &str, query: &str) -> DbResult<ObjectId> {
+        let run = IngestionRun {
+            id: ObjectId::new(),
+            mode: mode.to_owned(),
+            query: query.to_owned(),
+            status: RunStatus::Running,
+            started_at: DateTime::now(),
+            finished_at: None,
+            duration_secs: None,
+            stats: RunStats::default(),
+        };
+
+        self.collection.insert_one(&run).await?;
+        Ok(run.id)
+    }
+
+    /// Records the end of a run with its final status and statistics.
+    pub async fn finish(
+        &self,
+        id: ObjectId,
+        status: RunStatus,
+        stats: &RunStats,
+        duration_secs: f64,
+    ) -> DbResult<()> {
+        let mut update = mongodb::bson::to_document(stats)?;
+        update.insert("status", to_bson(&status)?);
+        update.insert("finished_at", DateTime::now());
+        update.insert("duration_secs", duration_secs);
+
+        self.collection
+            .update_one(doc! { "_id": id }, doc! { "$set": update })
+            .await?;
+        Ok(())
+    }
+
+    /// Retrieves the most recent runs, newest first.
+    pub async fn recent(&self, limit: i64) -> DbResult<Vec<IngestionRun>> {
+        let find_options = FindOptions::builder()
+            .sort(doc! { "started_at": -1 })
+            .limit(limit)
+            .build();
+
+        let runs = self
+            .collection
+            .find(doc! {})
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(runs)
+    }
+
+    /// Retrieves the most recent run of `mode` that finished without aborting.
+    ///
+    /// # Arguments
+    /// * `mode` - What the run did, e.g. `collect`.
+    pub async fn latest_completed(&self, mode: &str) -> DbResult<Option<IngestionRun>> {
+        let statuses = [RunStatus::Completed, RunStatus::CompletedWithErrors];
+        Ok(self
+            .collection
+            .find_one(doc! { "mode": mode, "status": { "$in": to_bson(&statuses)? } })
+            .sort(doc! { "started_at": -1 })
+            .await?)
+    }
+
+    /// Retrieves a single run by its ID.
+    pub async fn find(&self, id: ObjectId) -> DbResult<Option<IngestionRun>> {
+        Ok(self.collection.find_one(doc! { "_id": id }).await?)
+    }
+}
@@ -0,0 +1,162 @@
+//! Contains the repository logic for the `share_rates` collection.
+//! The channel share rate is the field that matters most to us, so every
+//! change of it is kept as its own document, which makes it cheap to report
+//! on the changes of a single run.
+
+use super::{collections, DbResult};
+use futures_util::stream::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    options::FindOptions,
+    Collection,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+use tunecore::models::CommunitySong;
+
+/// A share rate of a song, recorded when it was first seen or changed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ShareRateChange {
+    /// The ID of the song.
+    pub song_id: u64,
+    /// The share rate in percent.
+    pub share_rate: f64,
+    /// The previously recorded share rate, or `None` for the first record.
+    pub previous: Option<f64>,
+    /// The share rate as returned by the API.
+    pub raw: String,
+    /// The ingestion run that observed the share rate.
+    pub run_id: ObjectId,
+    /// When the share rate was observed.
+    pub observed_at: DateTime,
+}
+
+/// The latest recorded share rate of a song.
+#[derive(Deserialize)]
+struct LatestShareRate {
+    #[serde(rename = "_id")]
+    song_id: u64,
+    share_rate: f64,
+}
+
+/// A repository for handling database operations on the `share_rates` collection.
+#[derive(Clone, Debug)]
+pub struct ShareRatesRepo {
+    collection: Collection<ShareRateChange>,
+}
+
+impl ShareRatesRepo {
+    /// Creates a new `ShareRatesRepo`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the `mongodb::Database` instance.
+    pub(super) fn new(db: &mongodb::Database) -> Self {
+        Self {
+            collection: db.collection(collections::SHARE_RATES),
+        }
+    }
+
+    /// Records the share rates of `songs` that differ from the latest recorded ones.
+    ///
+    /// Songs whose share rate cannot be parsed are skipped with a warning.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the number of share rates recorded.
+    pub async fn record(&self, songs: &[CommunitySong], run_id: ObjectId) -> DbResult<usize> {
+        if songs.is_empty() {
+            return Ok(0);
+        }
+
+        let latest = self.latest(songs).await?;
+        let now = DateTime::now();
+        let changes: Vec<ShareRateChange> = songs
+            .iter()
+            .filter_map(|song| {
+                let Some(share_rate) = song.channel_share_percent() else {
+                    warn!(
+                        song_id = song.id,
+                        raw = song.channel_share_percent_str,
+                        "Could not parse share rate."
+                    );
+                    return None;
+                };
+                let previous = latest.get(&song.id).copied();
+                (previous != Some(share_rate)).then(|| ShareRateChange {
+                    song_id: song.id,
+                    share_rate,
+                    previous,
+                    raw: song.channel_share_percent_str.clone(),
+                    run_id,
+                    observed_at: now,
+                })
+            })
+            .collect();
+
+        if !changes.is_empty() {
+            self.collection.insert_many(&changes).await?;
+        }
+        Ok(changes.len())
+    }
+
+    /// Retrieves the latest recorded share rate of each of `songs`.
+    async fn latest(&self, songs: &[CommunitySong]) -> DbResult<HashMap<u64, f64>> {
+        let ids: Vec<i64> = songs.iter().map(|song| song.id as i64).collect();
+        let pipeline = vec![
+            doc! { "$match": { "song_id": { "$in": ids } } },
+            doc! { "$sort": { "observed_at": -1 } },
+            doc! { "$group": { "_id": "$song_id", "share_rate": { "$first": "$share_rate" } } },
+        ];
+
+        let latest: Vec<LatestShareRate> = self
+            .collection
+            .aggregate(pipeline)
+            .with_type::<LatestShareRate>()
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(latest
+            .into_iter()
+            .map(|latest| (latest.song_id, latest.share_rate))
+            .collect())
+    }
+
+    /// Retrieves the changes to existing share rates observed by a run.
+    ///
+    /// First records of newly seen songs are not included.
+    pub async fn changes_in_run(&self, run_id: ObjectId) -> DbResult<Vec<ShareRateChange>> {
+        let find_options = FindOptions::builder().sort(doc! { "song_id": 1 }).build();
+
+        let changes = self
+            .collection
+            .find(doc! { "run_id": run_id, "previous": { "$ne": null } })
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(changes)
+    }
+
+    /// Retrieves the share rate history of a song, oldest first.
+    ///
+    /// # Arguments
+    /// * `song_id` - The ID of the song.
+    #[allow(dead_code)]
+    pub async fn history(&self, song_id: u64) -> DbResult<Vec<ShareRateChange>> {
+        let find_options = FindOptions::builder()
+            .sort(doc! { "observed_at": 1 })
+            .build();
+
+        let history = self
+            .collection
+            .find(doc! { "song_id": song_id as i64 })
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(history)
+    }
+}
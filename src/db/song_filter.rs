@@ -0,0 +1,632 @@
+//! Contains the typed filter, sort order and options for querying stored
+//! songs, their translation into MongoDB documents, and the cursors used to
+//! page through songs by key.
+
+use super::{release_date, DbError, DbResult};
+use mongodb::bson::{doc, from_slice, to_vec, Bson, Document};
+use tunecore::models::ReleaseDate;
+
+/// An inclusive range; either end may be left open.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Bounds<T> {
+    /// The lowest accepted value, if any.
+    pub min: Option<T>,
+    /// The highest accepted value, if any.
+    pub max: Option<T>,
+}
+
+impl<T> Bounds<T> {
+    /// Creates bounds from optional ends.
+    pub fn new(min: Option<T>, max: Option<T>) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns `true` if neither end is set.
+    pub fn is_open(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    /// Builds a query condition such as `{ "$gte": min, "$lte": max }`.
+    fn condition(&self, convert: impl Fn(&T) -> DbResult<Bson>) -> DbResult<Document> {
+        let mut condition = Document::new();
+        if let Some(min) = &self.min {
+            condition.insert("$gte", convert(min)?);
+        }
+        if let Some(max) = &self.max {
+            condition.insert("$lte", convert(max)?);
+        }
+        Ok(condition)
+    }
+}
+
+/// A filter on stored songs. Conditions that are set must all match.
+#[derive(Debug, Clone, Default)]
+pub struct SongFilter {
+    /// Matches songs in any of these genres.
+    pub genre_ids: Vec<u16>,
+    /// Matches songs with any of these moods.
+    pub mood_ids: Vec<u16>,
+    /// Matches songs whose BPM is in range.
+    pub bpm: Bounds<f32>,
+    /// Matches songs whose duration in seconds is in range.
+    pub duration: Bounds<f32>,
+    /// Matches songs whose channel share rate in percent is in range.
+    ///
+    /// The rate is stored as a string, so this condition is evaluated per
+    /// document and cannot use an index.
+    pub share_rate: Bounds<f64>,
+    /// Matches songs crediting this artist.
+    pub artist_id: Option<u64>,
+    /// Matches songs released in range.
+    pub street_date: Bounds<ReleaseDate>,
+    /// Matches songs whose title or artist names contain this text, ignoring case.
+    pub text: Option<String>,
+    /// Also matches songs that are no longer listed by the API.
+    pub include_removed: bool,
+}
+
+impl SongFilter {
+    /// Translates the filter into a MongoDB query document.
+    pub fn to_document(&self) -> DbResult<Document> {
+        let mut filter = Document::new();
+        if !self.genre_ids.is_empty() {
+            let genre_ids: Vec<i32> = self.genre_ids.iter().map(|&id| id.into()).collect();
+            filter.insert("genre_id", doc! { "$in": genre_ids });
+        }
+        if !self.mood_ids.is_empty() {
+            let mood_ids: Vec<i32> = self.mood_ids.iter().map(|&id| id.into()).collect();
+            filter.insert("mood_id", doc! { "$in": mood_ids });
+        }
+        if !self.bpm.is_open() {
+            filter.insert(
+                "bpm",
+                self.bpm.condition(|&bpm| Ok(Bson::Double(bpm.into())))?,
+            );
+        }
+        if !self.duration.is_open() {
+            let condition = self
+                .duration
+                .condition(|&duration| Ok(Bson::Double(duration.into())))?;
+            filter.insert("duration", condition);
+        }
+        if let Some(artist_id) = self.artist_id {
+            filter.insert("artists.artist_id", artist_id as i64);
+        }
+        if !self.street_date.is_open() {
+            let condition = self
+                .street_date
+                .condition(|date| Ok(Bson::DateTime(release_date::to_datetime(date))))?;
+            filter.insert("street_date", condition);
+        }
+        if let Some(text) = self.text.as_deref().filter(|text| !text.is_empty()) {
+            let pattern = escape_regex(text);
+            let matches: Vec<Document> = TEXT_FIELDS
+                .iter()
+                .map(|&field| doc! { field: { "$regex": &pattern, "$options": "i" } })
+                .collect();
+            filter.insert("$or", matches);
+        }
+        if !self.share_rate.is_open() {
+            filter.insert("$expr", self.share_rate_expression());
+        }
+        if !self.include_removed {
+            filter.insert("removed_at", Bson::Null);
+        }
+        Ok(filter)
+    }
+
+    /// Builds the aggregation expression comparing the parsed share rate with its bounds.
+    fn share_rate_expression(&self) -> Document {
+        let share_rate = doc! { "$convert": {
+            "input": { "$trim": { "input": { "$replaceAll": {
+                "input": "$channel_share_percent_str",
+                "find": "%",
+                "replacement": "",
+            } } } },
+            "to": "double",
+            "onError": null,
+            "onNull": null,
+        } };
+
+        let mut conditions = vec![doc! { "$ne": [&share_rate, null] }];
+        if let Some(min) = self.share_rate.min {
+            conditions.push(doc! { "$gte": [&share_rate, min] });
+        }
+        if let Some(max) = self.share_rate.max {
+            conditions.push(doc! { "$lte": [&share_rate, max] });
+        }
+        doc! { "$and": conditions }
+    }
+}
+
+/// The fields searched by [`SongFilter::text`].
+const TEXT_FIELDS: &[&str] = &[
+    "song_title.ja",
+    "song_title.en",
+    "song_title.ja_kana",
+    "artist_name.ja",
+    "artist_name.en",
+    "artist_name.ja_kana",
+    "artists.name.ja",
+    "artists.name.en",
+];
+
+/// Escapes the characters with a special meaning in regular expressions.
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The fields songs can be sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SongSortField {
+    /// The song ID.
+    #[default]
+    Id,
+    /// The release date.
+    StreetDate,
+    /// The beats per minute.
+    Bpm,
+    /// The duration.
+    Duration,
+    /// When the song was first saved.
+    FirstSeenAt,
+    /// When the song was last saved.
+    LastSeenAt,
+}
+
+impl SongSortField {
+    /// All fields songs can be sorted by.
+    const ALL: [SongSortField; 6] = [
+        SongSortField::Id,
+        SongSortField::StreetDate,
+        SongSortField::Bpm,
+        SongSortField::Duration,
+        SongSortField::FirstSeenAt,
+        SongSortField::LastSeenAt,
+    ];
+
+    /// Returns the sort field stored under `name`, if any.
+    fn from_field(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.field() == name)
+    }
+
+    /// Returns the name of the stored field.
+    pub fn field(self) -> &'static str {
+        match self {
+            SongSortField::Id => "id",
+            SongSortField::StreetDate => "street_date",
+            SongSortField::Bpm => "bpm",
+            SongSortField::Duration => "duration",
+            SongSortField::FirstSeenAt => "first_seen_at",
+            SongSortField::LastSeenAt => "last_seen_at",
+        }
+    }
+}
+
+/// The order songs are returned in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SongSort {
+    /// The field to sort by.
+    pub field: SongSortField,
+    /// Sorts from the highest value to the lowest.
+    pub descending: bool,
+}
+
+impl SongSort {
+    /// Translates the sort order into a MongoDB sort document.
+    ///
+    /// Songs with equal values are ordered by ID so the order is stable.
+    pub fn to_document(self) -> Document {
+        let direction = if self.descending { -1 } else { 1 };
+        let mut sort = doc! { self.field.field(): direction };
+        if self.field != SongSortField::Id {
+            sort.insert("id", direction);
+        }
+        sort
+    }
+}
+
+/// How the results of a song query are shaped.
+#[derive(Debug, Clone, Default)]
+pub struct SongQueryOptions {
+    /// The order of the results.
+    pub sort: SongSort,
+    /// The fields to return, such as `song_title.ja`, or all fields if empty.
+    ///
+    /// The result type must be able to deserialize the projected documents.
+    pub fields: Vec<String>,
+    /// The maximum number of songs to return, if any.
+    pub limit: Option<i64>,
+}
+
+impl SongQueryOptions {
+    /// Builds the projection document, or `None` to return whole documents.
+    pub fn projection(&self) -> Option<Document> {
+        if self.fields.is_empty() {
+            return None;
+        }
+        let mut projection = doc! { "_id": 0 };
+        for field in &self.fields {
+            projection.insert(field.as_str(), 1);
+        }
+        Some(projection)
+    }
+}
+
+/// A position in the songs ordered by a sort key and then by ID.
+///
+/// Unlike skipping a number of songs, continuing from a cursor costs the same
+/// on every page and never skips or repeats songs when others are added or
+/// changed in between. Cursors are passed to clients as opaque tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SongCursor {
+    /// The order of the songs.
+    sort: SongSort,
+    /// The sort key and ID of the last song returned, or `None` at the start.
+    last: Option<(Bson, u64)>,
+}
+
+impl SongCursor {
+    /// The version of the token format.
+    const TOKEN_VERSION: i32 = 1;
+
+    /// Returns a cursor before the first song in the given order.
+    pub fn start(sort: SongSort) -> Self {
+        Self { sort, last: None }
+    }
+
+    /// Returns the cursor continuing after a song with the given sort key and ID.
+    pub(super) fn after(&self, key: Bson, id: u64) -> Self {
+        Self {
+            sort: self.sort,
+            last: Some((key, id)),
+        }
+    }
+
+    /// Returns the order of the songs.
+    pub fn sort(&self) -> SongSort {
+        self.sort
+    }
+
+    /// Encodes the cursor as an opaque token.
+    pub fn token(&self) -> DbResult<String> {
+        let mut token = doc! {
+            "v": Self::TOKEN_VERSION,
+            "s": self.sort.field.field(),
+            "d": self.sort.descending,
+        };
+        if let Some((key, id)) = &self.last {
+            token.insert("k", key.clone());
+            token.insert("i", *id as i64);
+        }
+        Ok(to_vec(&token)?
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect())
+    }
+
+    /// Decodes a token created by [`SongCursor::token`].
+    pub fn parse(token: &str) -> DbResult<Self> {
+        let invalid = || DbError::InvalidCursor(token.to_owned());
+        if !token.len().is_multiple_of(2) || !token.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..token.len())
+            .step_by(2)
+            .map(|start| u8::from_str_radix(&token[start..start + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        let token: Document = from_slice(&bytes).map_err(|_| invalid())?;
+
+        if token.get_i32("v").ok() != Some(Self::TOKEN_VERSION) {
+            return Err(invalid());
+        }
+        let field = token
+            .get_str("s")
+            .ok()
+            .and_then(SongSortField::from_field)
+            .ok_or_else(invalid)?;
+        let descending = token.get_bool("d").map_err(|_| invalid())?;
+        let last = match (token.get("k"), token.get_i64("i")) {
+            (Some(key), Ok(id)) => Some((key.clone(), id as u64)),
+            (None, Err(_)) => None,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            sort: SongSort { field, descending },
+            last,
+        })
+    }
+
+    /// Builds the query matching the listed songs after the cursor.
+    ///
+    /// Queries only compare values of the same type, so a release date that
+    /// is not stored as a BSON date could never be paged past. Paging by
+    /// release date therefore leaves such songs out.
+    pub(super) fn filter(&self) -> Document {
+        let mut filter = self.condition();
+        filter.insert("removed_at", Bson::Null);
+        if self.sort.field == SongSortField::StreetDate {
+            filter = doc! { "$and": [filter, { "street_date": { "$type": "date" } }] };
+        }
+        filter
+    }
+
+    /// Builds the query condition matching the songs after the cursor.
+    pub(super) fn condition(&self) -> Document {
+        let Some((key, id)) = &self.last else {
+            return Document::new();
+        };
+        let (after, id_after) = if self.sort.descending {
+            ("$lt", doc! { "$lt": *id as i64 })
+        } else {
+            ("$gt", doc! { "$gt": *id as i64 })
+        };
+        if self.sort.field == SongSortField::Id {
+            return doc! { "id": id_after };
+        }
+
+        let field = self.sort.field.field();
+        let same_key = doc! { field: key.clone(), "id": id_after };
+        match key {
+            // Missing keys sort before all others, so only present keys follow
+            // them in ascending order, and nothing follows them in descending order.
+            Bson::Null if self.sort.descending => same_key,
+            Bson::Null => doc! { "$or": [same_key, { field: { "$ne": null } }] },
+            // In descending order, songs missing the key come after all others.
+            key if self.sort.descending => doc! {
+                "$or": [same_key, { field: { after: key.clone() } }, { field: null }]
+            },
+            key => doc! { "$or": [same_key, { field: { after: key.clone() } }] },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::DateTime;
+
+    #[test]
+    fn an_empty_filter_matches_listed_songs() {
+        assert_eq!(
+            SongFilter::default().to_document().unwrap(),
+            doc! { "removed_at": null }
+        );
+
+        let filter = SongFilter {
+            include_removed: true,
+            ..SongFilter::default()
+        };
+        assert!(filter.to_document().unwrap().is_empty());
+    }
+
+    #[test]
+    fn set_conditions_are_translated() {
+        let date = ReleaseDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let filter = SongFilter {
+            genre_ids: vec![1, 5],
+            bpm: Bounds::new(Some(90.0), None),
+            duration: Bounds::new(None, Some(180.0)),
+            artist_id: Some(42),
+            street_date: Bounds::new(Some(date), Some(date)),
+            include_removed: true,
+            ..SongFilter::default()
+        };
+
+        let released = Bson::DateTime(release_date::to_datetime(&date));
+        assert_eq!(
+            filter.to_document().unwrap(),
+            doc! {
+                "genre_id": { "$in": [1, 5] },
+                "bpm": { "$gte": 90.0 },
+                "duration": { "$lte": 180.0 },
+                "artists.artist_id": 42_i64,
+                "street_date": { "$gte": released.clone(), "$lte": released },
+            }
+        );
+    }
+
+    #[test]
+    fn text_is_matched_literally_in_every_text_field() {
+        let filter = SongFilter {
+            text: Some("a.b".to_owned()),
+            ..SongFilter::default()
+        };
+
+        let document = filter.to_document().unwrap();
+        let matches = document.get_array("$or").unwrap();
+        assert_eq!(matches.len(), TEXT_FIELDS.len());
+        assert_eq!(
+            matches[0],
+            Bson::Document(doc! { "song_title.ja": { "$regex": "a\\.b", "$options": "i" } })
+        );
+    }
+
+    #[test]
+    fn empty_text_is_ignored() {
+        let filter = SongFilter {
+            text: Some(String::new()),
+            ..SongFilter::default()
+        };
+
+        assert!(!filter.to_document().unwrap().contains_key("$or"));
+    }
+
+    #[test]
+    fn share_rate_bounds_become_an_expression() {
+        let filter = SongFilter {
+            share_rate: Bounds::new(Some(10.0), Some(50.0)),
+            ..SongFilter::default()
+        };
+
+        let document = filter.to_document().unwrap();
+        let conditions = document
+            .get_document("$expr")
+            .unwrap()
+            .get_array("$and")
+            .unwrap();
+        assert_eq!(conditions.len(), 3);
+    }
+
+    #[test]
+    fn regex_metacharacters_are_escaped() {
+        assert_eq!(escape_regex("plain テキスト"), "plain テキスト");
+        assert_eq!(
+            escape_regex(r"\.^$|?*+()[]{}"),
+            r"\\\.\^\$\|\?\*\+\(\)\[\]\{\}"
+        );
+    }
+
+    fn sort(field: SongSortField, descending: bool) -> SongSort {
+        SongSort { field, descending }
+    }
+
+    #[test]
+    fn a_cursor_at_the_start_matches_every_song() {
+        let cursor = SongCursor::start(sort(SongSortField::Bpm, false));
+
+        assert!(cursor.condition().is_empty());
+    }
+
+    #[test]
+    fn a_cursor_by_id_compares_only_ids() {
+        let ascending = SongCursor::start(SongSort::default()).after(Bson::Int64(7), 7);
+        let descending = SongCursor::start(sort(SongSortField::Id, true)).after(Bson::Int64(7), 7);
+
+        assert_eq!(ascending.condition(), doc! { "id": { "$gt": 7_i64 } });
+        assert_eq!(descending.condition(), doc! { "id": { "$lt": 7_i64 } });
+    }
+
+    #[test]
+    fn ascending_cursors_continue_with_equal_or_higher_keys() {
+        let cursor =
+            SongCursor::start(sort(SongSortField::Bpm, false)).after(Bson::Double(120.0), 7);
+
+        assert_eq!(
+            cursor.condition(),
+            doc! { "$or": [
+                { "bpm": 120.0, "id": { "$gt": 7_i64 } },
+                { "bpm": { "$gt": 120.0 } },
+            ] }
+        );
+    }
+
+    #[test]
+    fn descending_cursors_continue_with_lower_or_missing_keys() {
+        let cursor =
+            SongCursor::start(sort(SongSortField::Bpm, true)).after(Bson::Double(120.0), 7);
+
+        assert_eq!(
+            cursor.condition(),
+            doc! { "$or": [
+                { "bpm": 120.0, "id": { "$lt": 7_i64 } },
+                { "bpm": { "$lt": 120.0 } },
+                { "bpm": null },
+            ] }
+        );
+    }
+
+    #[test]
+    fn cursors_after_a_missing_key() {
+        let ascending = SongCursor::start(sort(SongSortField::Bpm, false)).after(Bson::Null, 7);
+        let descending = SongCursor::start(sort(SongSortField::Bpm, true)).after(Bson::Null, 7);
+
+        assert_eq!(
+            ascending.condition(),
+            doc! { "$or": [
+                { "bpm": null, "id": { "$gt": 7_i64 } },
+                { "bpm": { "$ne": null } },
+            ] }
+        );
+        assert_eq!(
+            descending.condition(),
+            doc! { "bpm": null, "id": { "$lt": 7_i64 } }
+        );
+    }
+
+    #[test]
+    fn cursors_match_only_listed_songs() {
+        let cursor = SongCursor::start(SongSort::default()).after(Bson::Int64(7), 7);
+
+        assert_eq!(
+            cursor.filter(),
+            doc! { "id": { "$gt": 7_i64 }, "removed_at": null }
+        );
+    }
+
+    #[test]
+    fn cursors_by_release_date_match_only_stored_dates() {
+        let start = SongCursor::start(sort(SongSortField::StreetDate, false));
+        let released = Bson::DateTime(DateTime::from_millis(1_700_000_000_000));
+        let after = start.after(released.clone(), 7);
+
+        assert_eq!(
+            start.filter(),
+            doc! { "$and": [{ "removed_at": null }, { "street_date": { "$type": "date" } }] }
+        );
+        assert_eq!(
+            after.filter(),
+            doc! { "$and": [
+                {
+                    "$or": [
+                        { "street_date": released.clone(), "id": { "$gt": 7_i64 } },
+                        { "street_date": { "$gt": released } },
+                    ],
+                    "removed_at": null,
+                },
+                { "street_date": { "$type": "date" } },
+            ] }
+        );
+    }
+
+    #[test]
+    fn tokens_round_trip() {
+        let start = SongCursor::start(sort(SongSortField::StreetDate, true));
+        let after = start.after(Bson::DateTime(DateTime::from_millis(1_700_000_000_000)), 42);
+
+        for cursor in [start, after] {
+            let token = cursor.token().unwrap();
+            assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+            assert_eq!(SongCursor::parse(&token).unwrap(), cursor);
+        }
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        let valid = SongCursor::start(SongSort::default()).token().unwrap();
+        let encode = |token: Document| -> String {
+            to_vec(&token)
+                .unwrap()
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect()
+        };
+
+        let malformed = [
+            String::new(),
+            valid[1..].to_owned(),
+            format!("{}zz", &valid[..valid.len() - 2]),
+            "é".repeat(2),
+            "00".repeat(8),
+            encode(doc! { "v": 2, "s": "id", "d": false }),
+            encode(doc! { "v": 1, "s": "title", "d": false }),
+            encode(doc! { "v": 1, "s": "id" }),
+            encode(doc! { "v": 1, "s": "id", "d": false, "k": 1_i64 }),
+        ];
+        for token in malformed {
+            assert!(
+                matches!(SongCursor::parse(&token), Err(DbError::InvalidCursor(_))),
+                "{token:?} was accepted"
+            );
+        }
+    }
+}
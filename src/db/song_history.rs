@@ -0,0 +1,213 @@
+//! Contains the types stored in the `song_history` collection and the
+//! field-level diff used to produce them. A history entry is written
+//! whenever a crawl returns a song whose content differs from the stored copy.
+
+use mongodb::bson::{oid::ObjectId, Bson, DateTime, Document};
+use serde::{Deserialize, Serialize};
+
+/// Fields the repository adds to stored songs for its own bookkeeping.
+pub(super) const BOOKKEEPING_FIELDS: &[&str] = &[
+    "_id",
+    "content_hash",
+    "first_seen_at",
+    "last_seen_at",
+    "removed_at",
+];
+
+/// Per-request fields of a `CommunitySong` that are not part of its content.
+///
+/// Songs are no longer stored with these, but documents and history entries
+/// written before the canonical song form may still contain them.
+pub(super) const VOLATILE_FIELDS: &[&str] = &["index", "is_favorite"];
+
+/// A single changed field between two versions of a song.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    /// The dotted path of the field, e.g. `song_title.en`.
+    pub field: String,
+    /// The previous value, or `None` if the field was added.
+    pub old: Option<Bson>,
+    /// The new value, or `None` if the field was removed.
+    pub new: Option<Bson>,
+}
+
+/// A previous version of a song, replaced at `changed_at`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SongVersion {
+    /// The unique ID of the history entry.
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// The ID of the song.
+    pub song_id: u64,
+    /// The ingestion run that saw the change.
+    pub run_id: ObjectId,
+    /// When the previous version was replaced.
+    pub changed_at: DateTime,
+    /// The song as it was stored before the change.
+    pub previous: Document,
+    /// The fields that changed.
+    pub changes: Vec<FieldChange>,
+}
+
+/// Returns a stored song document without the repository's bookkeeping fields.
+pub(super) fn snapshot(document: &Document) -> Document {
+    without_fields(document, BOOKKEEPING_FIELDS)
+}
+
+/// Returns only the content fields of a song document.
+pub(super) fn content(document: &Document) -> Document {
+    without_fields(&snapshot(document), VOLATILE_FIELDS)
+}
+
+/// Returns a copy of `document` without the given top-level fields.
+fn without_fields(document: &Document, fields: &[&str]) -> Document {
+    document
+        .iter()
+        .filter(|(key, _)| !fields.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// Computes the field-level changes from `old` to `new`.
+///
+/// Embedded documents are compared field by field; arrays and all other
+/// values are compared as a whole.
+pub(super) fn diff(old: &Document, new: &Document) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_into("", old, new, &mut changes);
+    changes
+}
+
+/// Appends the changes between two documents below `prefix` to `changes`.
+fn diff_into(prefix: &str, old: &Document, new: &Document, changes: &mut Vec<FieldChange>) {
+    let path = |key: &str| {
+        if prefix.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{prefix}.{key}")
+        }
+    };
+
+    for (key, new_value) in new {
+        match (old.get(key), new_value) {
+            (Some(Bson::Document(old_doc)), Bson::Document(new_doc)) => {
+                diff_into(&path(key), old_doc, new_doc, changes);
+            }
+            (Some(old_value), _) if old_value == new_value => {}
+            (old_value, _) => changes.push(FieldChange {
+                field: path(key),
+                old: old_value.cloned(),
+                new: Some(new_value.clone()),
+            }),
+        }
+    }
+
+    for (key, old_value) in old {
+        if !new.contains_key(key) {
+            changes.push(FieldChange {
+                field: path(key),
+                old: Some(old_value.clone()),
+                new: None,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    fn change(field: &str, old: Option<Bson>, new: Option<Bson>) -> FieldChange {
+        FieldChange {
+            field: field.to_owned(),
+            old,
+            new,
+        }
+    }
+
+    #[test]
+    fn equal_documents_have_no_changes() {
+        let song = doc! { "id": 1_i64, "bpm": 120.0, "song_title": { "ja": "歌" } };
+
+        assert!(diff(&song, &song.clone()).is_empty());
+    }
+
+    #[test]
+    fn changed_added_and_removed_fields_are_reported() {
+        let old = doc! { "bpm": 120.0, "audio_url": "a.mp3" };
+        let new = doc! { "bpm": 128.0, "mood_id": 3 };
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                change("bpm", Some(Bson::Double(120.0)), Some(Bson::Double(128.0))),
+                change("mood_id", None, Some(Bson::Int32(3))),
+                change("audio_url", Some(Bson::from("a.mp3")), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn embedded_documents_are_compared_by_path() {
+        let old = doc! { "song_title": { "ja": "歌", "en": "Song" } };
+        let new = doc! { "song_title": { "ja": "歌", "en": "A Song", "ja_kana": "うた" } };
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                change(
+                    "song_title.en",
+                    Some(Bson::from("Song")),
+                    Some(Bson::from("A Song"))
+                ),
+                change("song_title.ja_kana", None, Some(Bson::from("うた"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_are_compared_as_a_whole() {
+        let old = doc! { "genre_id": [1, 2] };
+        let new = doc! { "genre_id": [2, 1] };
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![change(
+                "genre_id",
+                Some(Bson::Array(vec![Bson::Int32(1), Bson::Int32(2)])),
+                Some(Bson::Array(vec![Bson::Int32(2), Bson::Int32(1)]))
+            )]
+        );
+    }
+
+    #[test]
+    fn a_document_replacing_a_value_is_one_change() {
+        let old = doc! { "artist_name": "Artist" };
+        let new = doc! { "artist_name": { "ja": "Artist" } };
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![change(
+                "artist_name",
+                Some(Bson::from("Artist")),
+                Some(Bson::Document(doc! { "ja": "Artist" }))
+            )]
+        );
+    }
+
+    #[test]
+    fn content_ignores_bookkeeping_and_volatile_fields() {
+        let stored = doc! {
+            "_id": ObjectId::new(),
+            "id": 1_i64,
+            "content_hash": "abc",
+            "last_seen_at": DateTime::now(),
+            "index": 4,
+            "is_favorite": true,
+        };
+
+        assert_eq!(content(&stored), doc! { "id": 1_i64 });
+        assert!(snapshot(&stored).contains_key("index"));
+    }
+}
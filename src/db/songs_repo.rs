@@ -1,22 +1,328 @@
 //! Contains the repository logic for interacting with the `songs` collection.
 //! This module provides a structured and safe API for all database
 //! operations related to `CommunitySong` documents.
+//!
+//! Songs are stored as [`CanonicalSong`]s: the fields of a `CommunitySong`
+//! that describe the song itself, without its position in a response or
+//! the favorite flag of whoever fetched it.
 
-use super::{collections, DbResult};
-use futures_util::stream::TryStreamExt;
+use super::collaboration_graph::CollaborationGraph;
+use super::fingerprint;
+use super::release_date;
+use super::song_filter::{
+    Bounds, SongCursor, SongFilter, SongQueryOptions, SongSort, SongSortField,
+};
+use super::song_history::{self, SongVersion};
+use super::{collections, DbError, DbResult};
+use futures_util::stream::{Stream, TryStreamExt};
 use mongodb::{
-    bson::{doc, to_document},
+    bson::{doc, from_document, oid::ObjectId, to_document, Bson, DateTime, Document},
+    error::{ErrorKind, PartialBulkWriteResult, WriteError},
     options::{FindOptions, UpdateModifications, UpdateOneModel, WriteModel},
+    results::VerboseBulkWriteResult,
     Collection, Database,
 };
-use tunecore::models::CommunitySong;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{hash_map::Entry, HashMap};
+use tunecore::models::{Artist, ArtistName, CommunitySong, ReleaseDate, SongTitle};
+
+/// The canonical form of a song as it is persisted.
+///
+/// This is a `CommunitySong` without its per-request fields: `index` is only
+/// the song's position in one response and is recorded per query in
+/// `song_positions`, and `is_favorite` depends on the session making the request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CanonicalSong {
+    /// The unique identifier for the song.
+    pub id: u64,
+    /// The URL to the audio file for previewing the song.
+    pub audio_url: Option<String>,
+    /// The URL to the YouTube art track for the song.
+    pub youtube_art_track_url: Option<String>,
+    /// A Linkfire URL for the song.
+    pub linkcore_url: String,
+    /// The beats per minute (BPM) of the song.
+    pub bpm: f32,
+    /// The duration of the song in seconds.
+    pub duration: f32,
+    /// A list of genre IDs associated with the song.
+    pub genre_id: Vec<u16>,
+    /// The mood ID associated with the song.
+    pub mood_id: u16,
+    /// The URL to the album/song cover art.
+    pub jacket_url: String,
+    /// The release date of the song, stored as a BSON date.
+    #[serde(with = "release_date")]
+    pub street_date: ReleaseDate,
+    /// The localized titles of the song.
+    pub song_title: SongTitle,
+    /// The localized names of the primary artist.
+    pub artist_name: ArtistName,
+    /// A list of all artists credited on the song.
+    pub artists: Vec<Artist>,
+    /// The revenue share percentage for the channel, as a string.
+    pub channel_share_percent_str: String,
+}
+
+impl TryFrom<&CommunitySong> for CanonicalSong {
+    type Error = DbError;
+
+    /// Converts a song by encoding it and dropping the per-request fields, so
+    /// that both types share a single field list.
+    fn try_from(song: &CommunitySong) -> DbResult<Self> {
+        let mut document = to_document(song)?;
+        for field in song_history::VOLATILE_FIELDS {
+            document.remove(field);
+        }
+        Ok(from_document(document)?)
+    }
+}
+
+/// A song as stored in the `songs` collection, with its lifecycle timestamps.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredSong {
+    /// The song as last returned by the API.
+    #[serde(flatten)]
+    pub song: CanonicalSong,
+    /// When the song was first saved.
+    pub first_seen_at: Option<DateTime>,
+    /// When the song was last returned by a crawl, accurate to the UTC day.
+    pub last_seen_at: Option<DateTime>,
+    /// When a complete crawl first failed to return the song, if it is gone.
+    pub removed_at: Option<DateTime>,
+}
+
+/// A song that could not be written during a bulk save.
+#[derive(Clone, Debug)]
+pub struct SongWriteFailure {
+    /// The ID of the song that was not saved.
+    pub song_id: u64,
+    /// The server error code.
+    pub code: i32,
+    /// The server error message.
+    pub message: String,
+}
+
+/// What a call to [`SongsRepo::save_many`] did.
+#[derive(Clone, Debug, Default)]
+pub struct SaveStats {
+    /// The number of songs that already existed.
+    pub matched: u64,
+    /// The number of existing songs whose stored document changed.
+    pub modified: u64,
+    /// The number of songs inserted for the first time.
+    pub upserted: u64,
+    /// The number of songs not written because their content hash was unchanged.
+    pub skipped: u64,
+    /// The IDs of the songs inserted for the first time.
+    pub upserted_ids: Vec<u64>,
+    /// The songs that could not be written.
+    pub failures: Vec<SongWriteFailure>,
+}
+
+impl SaveStats {
+    /// Returns the number of existing songs that were left unchanged.
+    pub fn unchanged(&self) -> u64 {
+        self.matched.saturating_sub(self.modified) + self.skipped
+    }
+
+    /// Adds the results of another save to these stats.
+    pub fn add(&mut self, other: &SaveStats) {
+        self.matched += other.matched;
+        self.modified += other.modified;
+        self.upserted += other.upserted;
+        self.skipped += other.skipped;
+        self.upserted_ids.extend_from_slice(&other.upserted_ids);
+        self.failures.extend_from_slice(&other.failures);
+    }
+
+    /// Builds the stats from the verbose result of a bulk write of `songs`.
+    ///
+    /// Results are keyed by the index of the write model, which is the index
+    /// of the song in the slice.
+    fn from_result(songs: &[&CanonicalSong], result: &VerboseBulkWriteResult) -> Self {
+        let upserted = result
+            .update_results
+            .iter()
+            .filter(|(_, update)| update.upserted_id.is_some())
+            .map(|(index, _)| *index);
+
+        Self {
+            matched: result.summary.matched_count as u64,
+            modified: result.summary.modified_count as u64,
+            upserted: result.summary.upserted_count as u64,
+            skipped: 0,
+            upserted_ids: upserted_ids(songs, upserted),
+            failures: Vec::new(),
+        }
+    }
+}
+
+/// The resolution of `last_seen_at`, in milliseconds.
+///
+/// Seeing an unchanged song again only rewrites it once per UTC day, so
+/// repeated crawls do not write every document.
+const LAST_SEEN_RESOLUTION_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// Returns the start of the `last_seen_at` period containing `at`.
+fn last_seen_period(at: DateTime) -> DateTime {
+    let millis = at.timestamp_millis();
+    DateTime::from_millis(millis - millis.rem_euclid(LAST_SEEN_RESOLUTION_MILLIS))
+}
+
+/// Builds the filter matching the seen songs whose `last_seen_at` must be
+/// moved to `now`: those last seen in an earlier period, never stamped, or
+/// marked as removed.
+fn stale_last_seen_filter(ids: &[i64], now: DateTime) -> Document {
+    doc! {
+        "id": { "$in": ids },
+        "$or": [
+            { "last_seen_at": { "$lt": last_seen_period(now) } },
+            { "last_seen_at": null },
+            { "removed_at": { "$ne": null } },
+        ],
+    }
+}
+
+/// Builds the filter matching the listed songs that credit more than one artist.
+fn collaboration_filter() -> Document {
+    doc! { "artists.1": { "$exists": true }, "removed_at": Bson::Null }
+}
+
+/// Builds the aggregation counting the listed songs released per `period`.
+///
+/// `$dateTrunc` fails on values that are not dates, so release dates that are
+/// not stored as BSON dates are left out.
+fn release_counts_pipeline(
+    period: CalendarPeriod,
+    released: Bounds<ReleaseDate>,
+) -> DbResult<Vec<Document>> {
+    let mut filter = SongFilter {
+        street_date: released,
+        ..SongFilter::default()
+    }
+    .to_document()?;
+    match filter.get_document_mut("street_date") {
+        Ok(condition) => {
+            condition.insert("$type", "date");
+        }
+        Err(_) => {
+            filter.insert("street_date", doc! { "$type": "date" });
+        }
+    }
+    let truncate = match period {
+        CalendarPeriod::Week => doc! {
+            "date": "$street_date",
+            "unit": "week",
+            "startOfWeek": "monday",
+            "timezone": "UTC",
+        },
+        CalendarPeriod::Month => doc! {
+            "date": "$street_date",
+            "unit": "month",
+            "timezone": "UTC",
+        },
+    };
+    Ok(vec![
+        doc! { "$match": filter },
+        doc! { "$group": {
+            "_id": { "$dateTrunc": truncate },
+            "songs": { "$sum": 1 },
+        } },
+        doc! { "$sort": { "_id": 1 } },
+        doc! { "$project": { "_id": 0, "period_start": "$_id", "songs": 1 } },
+    ])
+}
+
+/// Converts songs to their canonical form, keeping only the last copy of
+/// each song ID at the position of its first.
+fn canonical_by_id(songs: &[CommunitySong]) -> DbResult<Vec<CanonicalSong>> {
+    let mut unique: Vec<CanonicalSong> = Vec::with_capacity(songs.len());
+    let mut positions: HashMap<u64, usize> = HashMap::with_capacity(songs.len());
+    for song in songs {
+        let song = CanonicalSong::try_from(song)?;
+        match positions.entry(song.id) {
+            Entry::Occupied(entry) => unique[*entry.get()] = song,
+            Entry::Vacant(entry) => {
+                entry.insert(unique.len());
+                unique.push(song);
+            }
+        }
+    }
+    Ok(unique)
+}
+
+/// Maps the indexes of upserting write models to the sorted IDs of their songs.
+fn upserted_ids(songs: &[&CanonicalSong], indexes: impl IntoIterator<Item = usize>) -> Vec<u64> {
+    let mut ids: Vec<u64> = indexes
+        .into_iter()
+        .filter_map(|index| songs.get(index).map(|song| song.id))
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// Maps the write errors of a bulk write, keyed by the index of the write
+/// model, to the songs that failed, sorted by song ID.
+fn write_failures(
+    songs: &[&CanonicalSong],
+    write_errors: &HashMap<usize, WriteError>,
+) -> Vec<SongWriteFailure> {
+    let mut failures: Vec<SongWriteFailure> = write_errors
+        .iter()
+        .filter_map(|(index, write_error)| {
+            songs.get(*index).map(|song| SongWriteFailure {
+                song_id: song.id,
+                code: write_error.code,
+                message: write_error.message.clone(),
+            })
+        })
+        .collect();
+    failures.sort_unstable_by_key(|failure| failure.song_id);
+    failures
+}
+
+/// The length of the periods releases are counted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarPeriod {
+    /// Weeks starting on Monday.
+    Week,
+    /// Calendar months.
+    Month,
+}
+
+/// The number of songs released in one period.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReleaseCount {
+    /// The first day of the period.
+    #[serde(with = "release_date")]
+    pub period_start: ReleaseDate,
+    /// The number of songs released in the period.
+    pub songs: u64,
+}
+
+/// A page of songs retrieved by key.
+#[derive(Serialize, Debug, Clone)]
+pub struct SongPage {
+    /// The songs on the page.
+    pub songs: Vec<CanonicalSong>,
+    /// The token continuing after the last song, or `None` after the last page.
+    pub next: Option<String>,
+}
+
+/// The ID of a stored song.
+#[derive(Deserialize)]
+struct SongId {
+    id: u64,
+}
 
 /// A repository for handling database operations on the `songs` collection.
 ///
 #[derive(Clone, Debug)]
 pub struct SongsRepo {
     database: Database,
-    collection: Collection<CommunitySong>,
+    collection: Collection<CanonicalSong>,
+    history: Collection<SongVersion>,
 }
 
 impl SongsRepo {
@@ -28,46 +334,554 @@ impl SongsRepo {
         Self {
             database: db.clone(),
             collection: db.collection(collections::SONGS),
+            history: db.collection(collections::SONG_HISTORY),
         }
     }
 
-    /// Saves a slice of `CommunitySong` documents to the database using an "upsert" strategy.
+    /// Saves a slice of `CommunitySong`s to the database as [`CanonicalSong`]s using an "upsert" strategy.
     ///
-    // If a song with the same `id` already exists, it will be updated.
+    /// If a song with the same `id` already exists, it will be updated.
     /// Otherwise, a new song document will be inserted. This prevents duplicates.
-    /// For this to be efficient, create a unique index in MongoDB on the `id` field.
+    /// The unique index on `id` that keeps this efficient is created by [`Db::init`].
+    ///
+    /// [`Db::init`]: super::Db::init
+    ///
+    /// Each song is stored with a `content_hash` of its content fields, and
+    /// songs whose hash matches the stored one are not rewritten at all.
+    ///
+    /// New songs are stamped with `first_seen_at`. Saved songs lose any
+    /// `removed_at` mark and get a fresh `last_seen_at` unless they were
+    /// already seen on the same UTC day; this is done in a separate update so
+    /// that the modified count only reflects changes to the song itself.
+    ///
+    /// When the content of an existing song changed, the stored copy is kept
+    /// in `song_history` together with a field-level diff and `run_id`.
+    ///
+    /// A song that appears more than once in `songs`, for example because it
+    /// moved between pages during a crawl, is saved once with its last copy.
+    ///
+    /// The writes are unordered, so a song that fails to save does not stop
+    /// the others. Such failures are reported per song in
+    /// [`SaveStats::failures`]; only errors affecting the whole batch, such as
+    /// write concern or connection errors, are returned as `Err`.
     ///
     /// # Arguments
     /// * `songs` - A slice of `CommunitySong` to save or update.
-    pub async fn save_many(&self, songs: &[CommunitySong]) -> DbResult<()> {
+    /// * `run_id` - The ingestion run the songs were fetched by.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the counts and the IDs of the inserted songs.
+    pub async fn save_many(
+        &self,
+        songs: &[CommunitySong],
+        run_id: ObjectId,
+    ) -> DbResult<SaveStats> {
         if songs.is_empty() {
-            return Ok(());
+            return Ok(SaveStats::default());
         }
+        let songs = canonical_by_id(songs)?;
+
+        let now = DateTime::now();
+        let ids: Vec<i64> = songs.iter().map(|song| song.id as i64).collect();
+        let stored_hashes: HashMap<u64, Option<String>> = self
+            .stored_documents(&ids, Some(doc! { "id": 1, "content_hash": 1 }))
+            .await?
+            .into_iter()
+            .map(|(id, document)| (id, document.get_str("content_hash").ok().map(str::to_owned)))
+            .collect();
 
-        let upserts = songs
+        let mut changed = Vec::new();
+        for song in &songs {
+            let hash = fingerprint::content_hash(song)?;
+            match stored_hashes.get(&song.id) {
+                Some(Some(stored_hash)) if *stored_hash == hash => {}
+                _ => changed.push((song, hash)),
+            }
+        }
+        let skipped = (songs.len() - changed.len()) as u64;
+
+        let existing: Vec<i64> = changed
+            .iter()
+            .filter(|(song, _)| stored_hashes.contains_key(&song.id))
+            .map(|(song, _)| song.id as i64)
+            .collect();
+        let stored = self.stored_documents(&existing, None).await?;
+
+        let mut versions = Vec::new();
+        let mut upserts = Vec::with_capacity(changed.len());
+
+        for (song, hash) in &changed {
+            let filter = doc! { "id": song.id as i64 };
+
+            let mut update_doc = to_document(song)?;
+            if let Some(previous) = stored.get(&song.id) {
+                let previous = song_history::snapshot(previous);
+                let changes = song_history::diff(
+                    &song_history::content(&previous),
+                    &song_history::content(&update_doc),
+                );
+                if !changes.is_empty() {
+                    versions.push(SongVersion {
+                        id: ObjectId::new(),
+                        song_id: song.id,
+                        run_id,
+                        changed_at: now,
+                        previous,
+                        changes,
+                    });
+                }
+            }
+            update_doc.insert("content_hash", hash);
+
+            let update = UpdateModifications::Document(doc! {
+                "$set": update_doc,
+                "$setOnInsert": { "first_seen_at": now },
+            });
+
+            let model = UpdateOneModel::builder()
+                .namespace(self.collection.namespace())
+                .filter(filter)
+                .update(update)
+                .upsert(true)
+                .build();
+
+            upserts.push(WriteModel::UpdateOne(model));
+        }
+
+        let written: Vec<&CanonicalSong> = changed.iter().map(|(song, _)| *song).collect();
+        let mut stats = if upserts.is_empty() {
+            SaveStats::default()
+        } else {
+            let client = self.database.client();
+            match client
+                .bulk_write(upserts)
+                .ordered(false)
+                .verbose_results()
+                .await
+            {
+                Ok(result) => SaveStats::from_result(&written, &result),
+                Err(error) => Self::partial_stats(&written, error)?,
+            }
+        };
+        stats.skipped = skipped;
+
+        let failed: Vec<u64> = stats.failures.iter().map(|f| f.song_id).collect();
+        let seen: Vec<i64> = songs
             .iter()
-            .map(|song| {
-                let filter = doc! { "id": song.id as i64 };
+            .filter(|song| !failed.contains(&song.id))
+            .map(|song| song.id as i64)
+            .collect();
+        versions.retain(|version| !failed.contains(&version.song_id));
+        if !versions.is_empty() {
+            self.history.insert_many(&versions).await?;
+        }
+
+        self.collection
+            .update_many(
+                stale_last_seen_filter(&seen, now),
+                doc! {
+                    "$set": { "last_seen_at": now },
+                    "$unset": { "removed_at": "" },
+                },
+            )
+            .await?;
 
-                let update_doc = to_document(song)?;
-                let update = UpdateModifications::Document(doc! { "$set": update_doc });
+        Ok(stats)
+    }
+
+    /// Retrieves the stored documents of the songs with the given IDs, keyed by song ID.
+    ///
+    /// # Arguments
+    /// * `ids` - The IDs of the songs.
+    /// * `projection` - The fields to retrieve, or `None` for whole documents.
+    async fn stored_documents(
+        &self,
+        ids: &[i64],
+        projection: Option<Document>,
+    ) -> DbResult<HashMap<u64, Document>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
 
-                let model = UpdateOneModel::builder()
-                    .namespace(self.collection.namespace())
-                    .filter(filter)
-                    .update(update)
-                    .upsert(true)
-                    .build();
+        let find_options = FindOptions::builder().projection(projection).build();
+        let documents: Vec<Document> = self
+            .collection
+            .clone_with_type::<Document>()
+            .find(doc! { "id": { "$in": ids } })
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
 
-                Ok(WriteModel::UpdateOne(model))
+        Ok(documents
+            .into_iter()
+            .filter_map(|document| {
+                let id = match document.get("id") {
+                    Some(Bson::Int64(id)) => *id as u64,
+                    Some(Bson::Int32(id)) => *id as u64,
+                    _ => return None,
+                };
+                Some((id, document))
             })
-            .collect::<DbResult<Vec<_>>>()?;
+            .collect())
+    }
+
+    /// Recovers the stats of a bulk write that failed for some songs only.
+    ///
+    /// Errors that are not per-song write errors are returned unchanged.
+    fn partial_stats(
+        songs: &[&CanonicalSong],
+        error: mongodb::error::Error,
+    ) -> DbResult<SaveStats> {
+        let ErrorKind::BulkWrite(bulk_error) = error.kind.as_ref() else {
+            return Err(error.into());
+        };
+        if !bulk_error.write_concern_errors.is_empty() {
+            return Err(error.into());
+        }
 
-        let client = self.database.client();
+        let mut stats = match &bulk_error.partial_result {
+            Some(PartialBulkWriteResult::Verbose(result)) => SaveStats::from_result(songs, result),
+            _ => SaveStats::default(),
+        };
+        stats.failures = write_failures(songs, &bulk_error.write_errors);
 
-        client.bulk_write(upserts).await?;
+        Ok(stats)
+    }
 
-        Ok(())
+    /// Marks every song not seen since the UTC day `crawl_started_at` falls on as removed.
+    ///
+    /// Call this only after a complete crawl of the whole catalog, so that
+    /// songs missing from it are really gone. As `last_seen_at` is only
+    /// accurate to the day, a song that disappears between two crawls on the
+    /// same day is marked by the first complete crawl of a later day. Songs
+    /// already marked keep their original `removed_at`.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the number of songs newly marked as removed.
+    pub async fn mark_removed(&self, crawl_started_at: DateTime) -> DbResult<u64> {
+        let result = self
+            .collection
+            .update_many(
+                doc! {
+                    "removed_at": null,
+                    "$or": [
+                        { "last_seen_at": { "$lt": last_seen_period(crawl_started_at) } },
+                        { "last_seen_at": { "$exists": false } },
+                    ],
+                },
+                doc! { "$set": { "removed_at": DateTime::now() } },
+            )
+            .await?;
+        Ok(result.modified_count)
+    }
+
+    /// Retrieves the songs first seen at or after `since`, newest first.
+    ///
+    /// # Arguments
+    /// * `since` - The earliest `first_seen_at` to include.
+    /// * `limit` - The maximum number of songs to retrieve.
+    #[allow(dead_code)]
+    pub async fn newly_added(&self, since: DateTime, limit: i64) -> DbResult<Vec<StoredSong>> {
+        self.find_stored(
+            doc! { "first_seen_at": { "$gte": since } },
+            doc! { "first_seen_at": -1 },
+            limit,
+        )
+        .await
+    }
+
+    /// Retrieves the songs marked as removed at or after `since`, most recent first.
+    ///
+    /// # Arguments
+    /// * `since` - The earliest `removed_at` to include.
+    /// * `limit` - The maximum number of songs to retrieve.
+    #[allow(dead_code)]
+    pub async fn recently_removed(&self, since: DateTime, limit: i64) -> DbResult<Vec<StoredSong>> {
+        self.find_stored(
+            doc! { "removed_at": { "$gte": since } },
+            doc! { "removed_at": -1 },
+            limit,
+        )
+        .await
+    }
+
+    /// Retrieves the previous versions of a song, oldest first.
+    ///
+    /// # Arguments
+    /// * `song_id` - The ID of the song.
+    #[allow(dead_code)]
+    pub async fn history(&self, song_id: u64) -> DbResult<Vec<SongVersion>> {
+        let find_options = FindOptions::builder()
+            .sort(doc! { "changed_at": 1 })
+            .build();
+
+        let versions = self
+            .history
+            .find(doc! { "song_id": song_id as i64 })
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(versions)
+    }
+
+    /// Reconstructs a song as it was stored at `at`.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the song, or `None` if it was not known yet
+    /// or had already been removed at that time.
+    #[allow(dead_code)]
+    pub async fn as_of(&self, song_id: u64, at: DateTime) -> DbResult<Option<CanonicalSong>> {
+        let Some(stored) = self
+            .collection
+            .clone_with_type::<StoredSong>()
+            .find_one(doc! { "id": song_id as i64 })
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        if stored
+            .first_seen_at
+            .is_some_and(|first_seen| first_seen > at)
+            || stored.removed_at.is_some_and(|removed| removed <= at)
+        {
+            return Ok(None);
+        }
+
+        let next_change = self
+            .history
+            .find_one(doc! { "song_id": song_id as i64, "changed_at": { "$gt": at } })
+            .sort(doc! { "changed_at": 1 })
+            .await?;
+
+        match next_change {
+            Some(version) => Ok(Some(from_document(version.previous)?)),
+            None => Ok(Some(stored.song)),
+        }
+    }
+
+    /// Retrieves the songs with the given IDs, in no particular order.
+    ///
+    /// # Arguments
+    /// * `ids` - The IDs of the songs.
+    pub async fn find_many(&self, ids: &[u64]) -> DbResult<Vec<CanonicalSong>> {
+        let ids: Vec<i64> = ids.iter().map(|&id| id as i64).collect();
+        let songs = self
+            .collection
+            .find(doc! { "id": { "$in": ids } })
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(songs)
+    }
+
+    /// Builds the artist collaboration graph from the credits of the listed songs.
+    ///
+    /// # Arguments
+    /// * `min_weight` - The number of shared songs an edge needs to be kept.
+    pub async fn collaboration_graph(&self, min_weight: u64) -> DbResult<CollaborationGraph> {
+        let credits = self.collaboration_credits().await?;
+        Ok(CollaborationGraph::build(credits, min_weight))
+    }
+
+    /// Retrieves the credited artists of every listed song with more than one artist.
+    async fn collaboration_credits(&self) -> DbResult<Vec<Vec<Artist>>> {
+        #[derive(Deserialize)]
+        struct Credits {
+            artists: Vec<Artist>,
+        }
+
+        let find_options = FindOptions::builder()
+            .projection(doc! { "_id": 0, "artists": 1 })
+            .build();
+        let credits: Vec<Credits> = self
+            .collection
+            .clone_with_type::<Credits>()
+            .find(collaboration_filter())
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(credits.into_iter().map(|credits| credits.artists).collect())
+    }
+
+    /// Retrieves the listed songs released between `from` and `to`, both
+    /// inclusive, in release order.
+    pub async fn released_between(
+        &self,
+        from: ReleaseDate,
+        to: ReleaseDate,
+    ) -> DbResult<Vec<CanonicalSong>> {
+        let filter = SongFilter {
+            street_date: Bounds::new(Some(from), Some(to)),
+            ..SongFilter::default()
+        };
+        let options = SongQueryOptions {
+            sort: SongSort {
+                field: SongSortField::StreetDate,
+                descending: false,
+            },
+            ..SongQueryOptions::default()
+        };
+        self.query(&filter, &options).await?.try_collect().await
+    }
+
+    /// Retrieves the listed songs released after `today`, soonest first.
+    ///
+    /// # Arguments
+    /// * `today` - The current date; songs released on it are not included.
+    /// * `limit` - The maximum number of songs to retrieve.
+    pub async fn upcoming_releases(
+        &self,
+        today: ReleaseDate,
+        limit: i64,
+    ) -> DbResult<Vec<CanonicalSong>> {
+        let filter = doc! {
+            "street_date": { "$gt": release_date::to_datetime(&today) },
+            "removed_at": null,
+        };
+        let find_options = FindOptions::builder()
+            .sort(doc! { "street_date": 1, "id": 1 })
+            .limit(limit)
+            .build();
+
+        let songs = self
+            .collection
+            .find(filter)
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(songs)
+    }
+
+    /// Counts the listed songs released per week or month.
+    ///
+    /// # Arguments
+    /// * `period` - The length of the periods to count in.
+    /// * `released` - The release dates to count; either end may be open.
+    ///
+    /// # Returns
+    /// A `DbResult` containing the periods with at least one release, in order.
+    pub async fn release_counts(
+        &self,
+        period: CalendarPeriod,
+        released: Bounds<ReleaseDate>,
+    ) -> DbResult<Vec<ReleaseCount>> {
+        let counts = self
+            .collection
+            .aggregate(release_counts_pipeline(period, released)?)
+            .with_type::<ReleaseCount>()
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(counts)
+    }
+
+    /// Streams the stored songs matching `filter`.
+    ///
+    /// Songs are read from the server in batches while the stream is
+    /// consumed, so large result sets are never held in memory at once.
+    ///
+    /// # Arguments
+    /// * `filter` - The conditions the songs must match.
+    /// * `options` - The order, fields and number of songs to return. `T` must
+    ///   be able to deserialize the projected documents, e.g. `CanonicalSong`
+    ///   without a projection or `Document` for any projection.
+    pub async fn query<T>(
+        &self,
+        filter: &SongFilter,
+        options: &SongQueryOptions,
+    ) -> DbResult<impl Stream<Item = DbResult<T>>>
+    where
+        T: DeserializeOwned + Send + Sync + Unpin,
+    {
+        let find_options = FindOptions::builder()
+            .sort(options.sort.to_document())
+            .projection(options.projection())
+            .limit(options.limit)
+            .build();
+
+        let cursor = self
+            .collection
+            .clone_with_type::<T>()
+            .find(filter.to_document()?)
+            .with_options(find_options)
+            .await?;
+
+        Ok(cursor.map_err(DbError::from))
+    }
+
+    /// Retrieves stored songs matching `filter` in the given order.
+    async fn find_stored(
+        &self,
+        filter: Document,
+        sort: Document,
+        limit: i64,
+    ) -> DbResult<Vec<StoredSong>> {
+        let find_options = FindOptions::builder().sort(sort).limit(limit).build();
+
+        let songs = self
+            .collection
+            .clone_with_type::<StoredSong>()
+            .find(filter)
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(songs)
+    }
+
+    /// Retrieves the songs after `cursor` in the cursor's order.
+    ///
+    /// Songs are ordered by the sort key and then by ID, and the returned page
+    /// carries the cursor for the next page. Prefer this over
+    /// [`get_paged`](Self::get_paged), whose cost grows with every page and
+    /// which skips or repeats songs when the collection changes in between.
+    /// Songs that are no longer listed are skipped.
+    ///
+    /// # Arguments
+    /// * `cursor` - Where to continue, e.g. [`SongCursor::start`] for the first page.
+    /// * `limit` - The maximum number of songs to retrieve.
+    pub async fn list_after(&self, cursor: &SongCursor, limit: i64) -> DbResult<SongPage> {
+        let limit = limit.max(1);
+        let find_options = FindOptions::builder()
+            .sort(cursor.sort().to_document())
+            .limit(limit)
+            .build();
+
+        let documents: Vec<Document> = self
+            .collection
+            .clone_with_type::<Document>()
+            .find(cursor.filter())
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        let key_field = cursor.sort().field.field();
+        let next = match documents.last() {
+            Some(last) if documents.len() as i64 == limit => {
+                let key = last.get(key_field).cloned().unwrap_or(Bson::Null);
+                let id = from_document::<SongId>(last.clone())?.id;
+                Some(cursor.after(key, id).token()?)
+            }
+            _ => None,
+        };
+        let songs = documents
+            .into_iter()
+            .map(from_document)
+            .collect::<Result<Vec<CanonicalSong>, _>>()?;
+
+        Ok(SongPage { songs, next })
     }
 
     /// Retrieves a paginated list of songs from the collection.
@@ -79,7 +893,7 @@ impl SongsRepo {
     /// * `page` - The page number to retrieve (1-based). If 0 is passed, it defaults to 1.
     /// * `per_page` - The maximum number of songs to retrieve for the page.
     #[allow(dead_code)]
-    pub async fn get_paged(&self, page: u64, per_page: u64) -> DbResult<Vec<CommunitySong>> {
+    pub async fn get_paged(&self, page: u64, per_page: u64) -> DbResult<Vec<CanonicalSong>> {
         let page = page.max(1);
         // Calculate the number of documents to skip to get to the desired page.
         let skip = (page - 1) * per_page;
@@ -114,3 +928,209 @@ impl SongsRepo {
         Ok(result.deleted_count)
     }
 }
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use mongodb::error::{BulkWriteError, Error};
+
+    pub(in crate::db) fn community_song(id: u64, title: &str) -> CommunitySong {
+        CommunitySong {
+            id,
+            index: 0,
+            audio_url: None,
+            youtube_art_track_url: None,
+            linkcore_url: String::new(),
+            bpm: 120.0,
+            duration: 180.0,
+            genre_id: vec![1],
+            mood_id: 1,
+            jacket_url: String::new(),
+            street_date: ReleaseDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            song_title: SongTitle {
+                ja: title.to_owned(),
+                en: None,
+                ja_kana: None,
+            },
+            artist_name: ArtistName {
+                ja: String::new(),
+                en: None,
+                ja_kana: None,
+            },
+            artists: Vec::new(),
+            channel_share_percent_str: "50".to_owned(),
+            is_favorite: false,
+        }
+    }
+
+    fn song(id: u64) -> CanonicalSong {
+        CanonicalSong::try_from(&community_song(id, "title")).unwrap()
+    }
+
+    fn write_error(code: i32, message: &str) -> WriteError {
+        from_document(doc! { "code": code, "errmsg": message }).unwrap()
+    }
+
+    #[test]
+    fn canonical_songs_have_every_field_but_the_volatile_ones() {
+        let song = community_song(1, "歌");
+        let mut expected = to_document(&song).unwrap();
+        for field in song_history::VOLATILE_FIELDS {
+            expected.remove(field);
+        }
+
+        let canonical = CanonicalSong::try_from(&song).unwrap();
+
+        let keys = |document: &Document| document.keys().cloned().collect::<Vec<_>>();
+        assert_eq!(keys(&to_document(&canonical).unwrap()), keys(&expected));
+        assert_eq!(canonical.street_date, song.street_date);
+    }
+
+    #[test]
+    fn release_dates_are_stored_as_dates() {
+        let stored = to_document(&song(1)).unwrap();
+        let released = ReleaseDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        assert_eq!(
+            stored.get("street_date"),
+            Some(&Bson::DateTime(release_date::to_datetime(&released)))
+        );
+    }
+
+    #[test]
+    fn release_counts_skip_dates_not_stored_as_dates() {
+        let from = ReleaseDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let open = release_counts_pipeline(CalendarPeriod::Month, Bounds::default()).unwrap();
+        let bounded =
+            release_counts_pipeline(CalendarPeriod::Week, Bounds::new(Some(from), None)).unwrap();
+
+        assert_eq!(
+            open[0],
+            doc! { "$match": { "street_date": { "$type": "date" }, "removed_at": null } }
+        );
+        assert_eq!(
+            bounded[0],
+            doc! { "$match": {
+                "street_date": {
+                    "$gte": release_date::to_datetime(&from),
+                    "$type": "date",
+                },
+                "removed_at": null,
+            } }
+        );
+    }
+
+    #[test]
+    fn duplicate_songs_keep_the_last_copy_in_first_position() {
+        let songs = [
+            community_song(1, "old"),
+            community_song(2, "other"),
+            community_song(1, "new"),
+        ];
+
+        let unique = canonical_by_id(&songs).unwrap();
+
+        let titles: Vec<(u64, &str)> = unique
+            .iter()
+            .map(|song| (song.id, song.song_title.ja.as_str()))
+            .collect();
+        assert_eq!(titles, vec![(1, "new"), (2, "other")]);
+    }
+
+    #[test]
+    fn upserted_indexes_map_to_sorted_song_ids() {
+        let songs = [song(30), song(10), song(20)];
+        let written: Vec<&CanonicalSong> = songs.iter().collect();
+
+        assert_eq!(upserted_ids(&written, [2, 0]), vec![20, 30]);
+    }
+
+    #[test]
+    fn upserted_indexes_outside_the_batch_are_ignored() {
+        let songs = [song(10)];
+        let written: Vec<&CanonicalSong> = songs.iter().collect();
+
+        assert_eq!(upserted_ids(&written, [0, 5]), vec![10]);
+    }
+
+    #[test]
+    fn write_errors_map_to_the_failed_songs() {
+        let songs = [song(30), song(10), song(20)];
+        let written: Vec<&CanonicalSong> = songs.iter().collect();
+        let errors = HashMap::from([
+            (0, write_error(11000, "duplicate key")),
+            (2, write_error(121, "validation failed")),
+            (7, write_error(2, "out of range")),
+        ]);
+
+        let failures = write_failures(&written, &errors);
+
+        let mapped: Vec<(u64, i32)> = failures.iter().map(|f| (f.song_id, f.code)).collect();
+        assert_eq!(mapped, vec![(20, 121), (30, 11000)]);
+        assert_eq!(failures[0].message, "validation failed");
+    }
+
+    #[test]
+    fn partial_stats_keep_the_counts_and_failures() {
+        let songs = [song(10), song(20)];
+        let written: Vec<&CanonicalSong> = songs.iter().collect();
+        let mut result = VerboseBulkWriteResult::default();
+        result.summary.matched_count = 1;
+        result.summary.modified_count = 1;
+        let mut bulk_error = BulkWriteError::default();
+        bulk_error.write_errors = HashMap::from([(1, write_error(11000, "duplicate key"))]);
+        bulk_error.partial_result = Some(PartialBulkWriteResult::Verbose(result));
+
+        let stats =
+            SongsRepo::partial_stats(&written, Error::from(ErrorKind::BulkWrite(bulk_error)))
+                .unwrap();
+
+        assert_eq!(stats.matched, 1);
+        assert_eq!(stats.modified, 1);
+        assert_eq!(stats.failures.len(), 1);
+        assert_eq!(stats.failures[0].song_id, 20);
+    }
+
+    #[test]
+    fn partial_stats_return_other_errors() {
+        let songs = [song(10)];
+        let written: Vec<&CanonicalSong> = songs.iter().collect();
+        let error = Error::from(std::io::Error::other("connection reset"));
+
+        assert!(SongsRepo::partial_stats(&written, error).is_err());
+    }
+
+    #[test]
+    fn last_seen_periods_start_at_midnight_utc() {
+        let midnight = DateTime::from_millis(1_709_251_200_000);
+        let evening = DateTime::from_millis(1_709_251_200_000 + 20 * 60 * 60 * 1000);
+
+        assert_eq!(last_seen_period(midnight), midnight);
+        assert_eq!(last_seen_period(evening), midnight);
+    }
+
+    #[test]
+    fn songs_seen_earlier_the_same_day_are_not_rewritten() {
+        let now = DateTime::from_millis(1_709_251_200_000 + 20 * 60 * 60 * 1000);
+
+        assert_eq!(
+            stale_last_seen_filter(&[1, 2], now),
+            doc! {
+                "id": { "$in": [1_i64, 2_i64] },
+                "$or": [
+                    { "last_seen_at": { "$lt": DateTime::from_millis(1_709_251_200_000) } },
+                    { "last_seen_at": null },
+                    { "removed_at": { "$ne": null } },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn collaborations_count_only_listed_songs() {
+        assert_eq!(
+            collaboration_filter(),
+            doc! { "artists.1": { "$exists": true }, "removed_at": null }
+        );
+    }
+}
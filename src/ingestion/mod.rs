@@ -1,3 +1,5 @@
+pub mod query;
 pub mod songs_collector;
 
-pub use songs_collector::SongsCollector;
+pub use query::CrawlQuery;
+pub use songs_collector::{CollectOptions, SongsCollector};
@@ -0,0 +1,49 @@
+//! Describes the API queries a crawl can walk through.
+
+use tunecore::{
+    creators::{CreatorsBuilder, SortBy},
+    TunecoreClient,
+};
+
+/// A songs listing that a crawl pages through.
+///
+/// The query's [`key`](CrawlQuery::key) identifies it in the database, e.g.
+/// to match a crawl with its checkpoint.
+#[derive(Clone, Copy, Default)]
+pub struct CrawlQuery {
+    /// The sort order requested from the API, if any.
+    pub sort: Option<SortBy>,
+}
+
+impl CrawlQuery {
+    /// Returns the query listing all songs by popularity.
+    pub fn popularity() -> Self {
+        Self {
+            sort: Some(SortBy::Popularity),
+        }
+    }
+
+    /// Returns `true` if crawling this query records popularity ranks.
+    pub fn tracks_popularity(&self) -> bool {
+        matches!(self.sort, Some(SortBy::Popularity))
+    }
+
+    /// Returns a stable key identifying this query.
+    pub fn key(&self) -> String {
+        match self.sort {
+            None => "songs".to_owned(),
+            Some(SortBy::Popularity) => "songs?sort=popular_rank:desc".to_owned(),
+            Some(SortBy::ShareRateDescending) => "songs?sort=share_rate:desc".to_owned(),
+            Some(SortBy::ShareRateAscending) => "songs?sort=share_rate:asc".to_owned(),
+        }
+    }
+
+    /// Builds a request for the first page of this query.
+    pub fn request<'a>(&self, client: &'a TunecoreClient) -> CreatorsBuilder<'a> {
+        let builder = client.creators().songs();
+        match self.sort {
+            Some(sort) => builder.sort(sort),
+            None => builder,
+        }
+    }
+}
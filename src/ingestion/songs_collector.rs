@@ -1,103 +1,625 @@
-use crate::db::{DbResult, SongsRepo};
+use super::CrawlQuery;
+use crate::db::{
+    ArtistsRepo, CheckpointsRepo, CrawlCheckpoint, Db, DbResult, FailedPagesRepo, PopularityRepo,
+    PositionsRepo, RunStats, RunStatus, RunsRepo, SaveStats, ShareRatesRepo, SongPosition,
+    SongsRepo,
+};
 use futures::stream;
-use futures_util::{stream::TryStreamExt, StreamExt};
-use std::sync::Arc;
+use futures_util::StreamExt;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use std::collections::{BTreeSet, HashSet};
+use std::time::Instant;
 use tracing::{debug, info, instrument, warn};
 use tunecore::{models::CommunitySong, TunecoreClient};
 
+/// The number of songs buffered before they are flushed to the database.
+const BATCH_SIZE: usize = 1000;
+
+/// Options controlling a single run of [`SongsCollector::collect_all`].
+#[derive(Clone, Debug)]
+pub struct CollectOptions {
+    /// The maximum number of pages fetched concurrently.
+    pub max_concurrency: usize,
+    /// Ignores any existing checkpoint and crawls every page again.
+    pub fresh_start: bool,
+}
+
+/// The outcome of a crawl, counting pages rather than failing on the first error.
+#[derive(Clone, Debug, Default)]
+pub struct CollectReport {
+    /// The ingestion run the crawl was recorded as.
+    pub run_id: Option<ObjectId>,
+    /// The total number of songs reported by the API, if the first page was fetched.
+    pub api_total: Option<usize>,
+    /// The number of pages fetched and saved successfully.
+    pub pages_succeeded: usize,
+    /// The number of pages that failed and were recorded in `failed_pages`.
+    pub pages_failed: usize,
+    /// What saving the fetched songs did.
+    pub saved: SaveStats,
+    /// The number of stored songs the crawl found to be removed from the catalog.
+    pub songs_removed: u64,
+    /// The errors of the pages that failed.
+    pub errors: Vec<String>,
+}
+
+impl CollectReport {
+    /// Returns `true` if any page or song could not be saved.
+    fn has_errors(&self) -> bool {
+        self.pages_failed > 0 || !self.saved.failures.is_empty()
+    }
+
+    /// Converts the report into the statistics stored with the run.
+    fn to_run_stats(&self) -> RunStats {
+        let song_errors = self.saved.failures.iter().map(|failure| {
+            format!(
+                "song {}: [{}] {}",
+                failure.song_id, failure.code, failure.message
+            )
+        });
+
+        RunStats {
+            api_total: self.api_total.map(|total| total as u64),
+            pages_fetched: self.pages_succeeded as u64,
+            pages_failed: self.pages_failed as u64,
+            songs_inserted: self.saved.upserted,
+            songs_updated: self.saved.modified,
+            songs_unchanged: self.saved.unchanged(),
+            songs_removed: self.songs_removed,
+            errors: self.errors.iter().cloned().chain(song_errors).collect(),
+        }
+    }
+}
+
+/// The result of fetching a single page.
+enum PageOutcome {
+    /// The page was fetched; its songs still need to be saved.
+    Fetched(u32, Vec<CommunitySong>),
+    /// The page could not be fetched.
+    Failed(u32, String),
+}
+
+/// The kind of crawl an ingestion run performs.
+#[derive(Clone, Copy, Debug)]
+enum CrawlKind {
+    /// Crawls every page the checkpoint has not completed.
+    All,
+    /// Crawls only the pages recorded in `failed_pages`.
+    Failed,
+}
+
+impl CrawlKind {
+    /// Returns the mode the run is recorded with.
+    fn mode(self) -> &'static str {
+        match self {
+            CrawlKind::All => "collect",
+            CrawlKind::Failed => "retry_failed",
+        }
+    }
+}
+
+/// What every page of a single crawl shares.
+struct CrawlContext {
+    /// The key identifying the crawled query.
+    key: String,
+    /// The ingestion run performing the crawl.
+    run_id: ObjectId,
+    /// The number of songs per page the crawl was planned with, if known.
+    per_page: Option<u32>,
+    /// The time of the popularity snapshot the crawl records ranks for, if any.
+    snapshot_at: Option<DateTime>,
+}
+
+/// Songs fetched from the API that have not been flushed to the database yet.
+#[derive(Default)]
+struct PendingBatch {
+    songs: Vec<CommunitySong>,
+    positions: Vec<SongPosition>,
+    pages: Vec<u32>,
+}
+
+impl PendingBatch {
+    /// Adds the songs of a fetched page along with their positions in the query.
+    ///
+    /// The absolute rank of each song is only known when the page size is.
+    fn push_page(&mut self, context: &CrawlContext, page: u32, songs: Vec<CommunitySong>) {
+        let observed_at = DateTime::now();
+        self.positions
+            .extend(songs.iter().enumerate().map(|(offset, song)| {
+                SongPosition {
+                    query: context.key.clone(),
+                    song_id: song.id,
+                    page,
+                    index: song.index,
+                    rank: context
+                        .per_page
+                        .map(|per_page| (page as u64 - 1) * per_page as u64 + offset as u64 + 1),
+                    run_id: context.run_id,
+                    observed_at,
+                }
+            }));
+        self.songs.extend(songs);
+        self.pages.push(page);
+    }
+
+    /// Returns the pages the songs with the given IDs were fetched from.
+    fn pages_of(&self, song_ids: &HashSet<u64>) -> BTreeSet<u32> {
+        self.positions
+            .iter()
+            .filter(|position| song_ids.contains(&position.song_id))
+            .map(|position| position.page)
+            .collect()
+    }
+}
+
 /// A collector to store all community songs from TuneCore.
 ///
 /// This utility encapsulates the logic for fetching paginated data concurrently
-/// and saving it to a data repository in efficient batches.
-#[allow(dead_code)]
+/// and saving it to a data repository in efficient batches. Progress is
+/// checkpointed after every flush, so an interrupted crawl resumes where it stopped,
+/// and pages that fail are set aside in `failed_pages` instead of aborting the crawl.
+/// Every run is recorded in `ingestion_runs`, and crawls sorted by popularity
+/// also record each song's rank in `popularity_snapshots`.
 #[derive(Clone, Debug)]
 pub struct SongsCollector {
     client: TunecoreClient,
     songs_repo: SongsRepo,
+    artists_repo: ArtistsRepo,
+    checkpoints_repo: CheckpointsRepo,
+    failed_pages_repo: FailedPagesRepo,
+    positions_repo: PositionsRepo,
+    popularity_repo: PopularityRepo,
+    share_rates_repo: ShareRatesRepo,
+    runs_repo: RunsRepo,
 }
 
 impl SongsCollector {
     /// Creates a new instance of the song collector.
-    #[allow(dead_code)]
-    pub fn new(client: &TunecoreClient, songs_repo: &SongsRepo) -> Self {
+    pub fn new(client: &TunecoreClient, db: &Db) -> Self {
         Self {
             client: client.clone(),
-            songs_repo: songs_repo.clone(),
+            songs_repo: db.songs(),
+            artists_repo: db.artists(),
+            checkpoints_repo: db.checkpoints(),
+            failed_pages_repo: db.failed_pages(),
+            positions_repo: db.positions(),
+            popularity_repo: db.popularity(),
+            share_rates_repo: db.share_rates(),
+            runs_repo: db.runs(),
         }
     }
 
-    /// Fetches all songs from the API concurrently and saves them to the database.
-    #[instrument(skip_all, fields(concurrency = max_concurrency))]
-    #[allow(dead_code)]
-    pub async fn collect_all(&self, max_concurrency: usize) -> DbResult<()> {
-        const BATCH_SIZE: usize = 1000;
+    /// Fetches all songs of `query` from the API concurrently and saves them to the database.
+    ///
+    /// If an unfinished checkpoint with the same page size and a similar total
+    /// exists for the query, only the pages it has not completed are fetched,
+    /// unless `options.fresh_start` is set. Checkpoints that cannot be resumed
+    /// are discarded, and the reason is logged.
+    ///
+    /// Pages that fail to be fetched, including the first, are recorded in the
+    /// `failed_pages` collection and counted in the returned report; use
+    /// [`SongsCollector::retry_failed`] to fetch them again. The checkpoint
+    /// stays open until every page is saved.
+    #[instrument(skip_all, fields(query = %query.key(), concurrency = options.max_concurrency))]
+    pub async fn collect_all(
+        &self,
+        query: &CrawlQuery,
+        options: &CollectOptions,
+    ) -> DbResult<CollectReport> {
+        self.record_run(CrawlKind::All, query, options).await
+    }
 
-        let first_response = self.client.creators().songs().send().await?;
-        let total_songs = first_response.total;
-        let per_page = first_response.community_songs.len();
+    /// Re-fetches only the pages of `query` recorded in `failed_pages`.
+    ///
+    /// Pages that succeed are removed from the collection; pages that fail
+    /// again have their attempt count incremented. Once no failed pages remain
+    /// and every page of the crawl is saved, its checkpoint is finished.
+    #[instrument(skip_all, fields(query = %query.key(), concurrency = options.max_concurrency))]
+    pub async fn retry_failed(
+        &self,
+        query: &CrawlQuery,
+        options: &CollectOptions,
+    ) -> DbResult<CollectReport> {
+        self.record_run(CrawlKind::Failed, query, options).await
+    }
 
-        if per_page == 0 {
-            warn!("API returned 0 songs on the first page. No data to collect.");
-            return Ok(());
+    /// Records a run in `ingestion_runs` around a crawl.
+    ///
+    /// The crawl adds to the report as it goes, so a run that fails still
+    /// records the pages and songs it saved before the error.
+    async fn record_run(
+        &self,
+        kind: CrawlKind,
+        query: &CrawlQuery,
+        options: &CollectOptions,
+    ) -> DbResult<CollectReport> {
+        let mode = kind.mode();
+        let run_id = self.runs_repo.start(mode, &query.key()).await?;
+        info!(%run_id, mode, "Started ingestion run.");
+
+        let start_time = Instant::now();
+        let mut report = CollectReport {
+            run_id: Some(run_id),
+            ..CollectReport::default()
+        };
+        let result = match kind {
+            CrawlKind::All => self.crawl_all(query, options, run_id, &mut report).await,
+            CrawlKind::Failed => self.crawl_failed(query, options, run_id, &mut report).await,
+        };
+        let duration_secs = start_time.elapsed().as_secs_f64();
+
+        let status = match &result {
+            Err(e) => {
+                report.errors.push(e.to_string());
+                RunStatus::Failed
+            }
+            Ok(()) if report.has_errors() => RunStatus::CompletedWithErrors,
+            Ok(()) => RunStatus::Completed,
+        };
+        self.runs_repo
+            .finish(run_id, status, &report.to_run_stats(), duration_secs)
+            .await?;
+        info!(%run_id, ?status, "Recorded ingestion run.");
+
+        result.map(|()| report)
+    }
+
+    /// Crawls every page of `query` that the checkpoint has not completed.
+    ///
+    /// If the first page cannot be fetched, it is recorded as failed like any
+    /// other page, and the crawl continues with the plan of an unfinished
+    /// checkpoint if there is one. The checkpoint is only finished once every
+    /// page has been saved, so a crawl with failures stays resumable.
+    async fn crawl_all(
+        &self,
+        query: &CrawlQuery,
+        options: &CollectOptions,
+        run_id: ObjectId,
+        report: &mut CollectReport,
+    ) -> DbResult<()> {
+        let key = query.key();
+
+        if options.fresh_start && self.checkpoints_repo.delete(&key).await? {
+            info!("Discarded existing checkpoint for a fresh start.");
+        }
+        let stored = self.checkpoints_repo.find(&key).await?;
+
+        let (checkpoint, first_page) = match query.request(&self.client).send().await {
+            Ok(response) => {
+                let total_songs = response.total;
+                let per_page = response.community_songs.len();
+
+                if per_page == 0 {
+                    warn!("API returned 0 songs on the first page. No data to collect.");
+                    return Ok(());
+                }
+
+                let total_pages = total_songs.div_ceil(per_page);
+                info!(
+                    total_songs,
+                    per_page, total_pages, "Created collection plan."
+                );
+                report.api_total = Some(total_songs);
+
+                let checkpoint = self
+                    .resume_or_start(&key, stored, total_songs, per_page, total_pages)
+                    .await?;
+                (checkpoint, Some(response.community_songs))
+            }
+            Err(e) => {
+                let error = e.to_string();
+                warn!(page = 1, error, "Failed to fetch page.");
+                self.failed_pages_repo
+                    .record_failure(&key, 1, &error)
+                    .await?;
+                report.pages_failed += 1;
+                report.errors.push(format!("page 1: {error}"));
+
+                match stored.filter(|checkpoint| checkpoint.finished_at.is_none()) {
+                    Some(checkpoint) => {
+                        info!(
+                            completed_pages = checkpoint.completed_pages.len(),
+                            started_at = %checkpoint.started_at,
+                            "Continuing crawl from checkpoint without the first page."
+                        );
+                        (checkpoint, None)
+                    }
+                    None => {
+                        warn!("Cannot plan the crawl without the first page or a checkpoint.");
+                        return Ok(());
+                    }
+                }
+            }
+        };
+
+        let completed: HashSet<u32> = checkpoint.completed_pages.iter().copied().collect();
+        let context = CrawlContext {
+            key,
+            run_id,
+            per_page: Some(checkpoint.per_page),
+            snapshot_at: query.tracks_popularity().then_some(checkpoint.started_at),
+        };
+
+        if let Some(songs) = first_page.filter(|_| !completed.contains(&1)) {
+            let songs_saved = songs.len();
+            let mut first_batch = PendingBatch::default();
+            first_batch.push_page(&context, 1, songs);
+            self.flush(&context, &mut first_batch, report).await?;
+            debug!(page = 1, songs_saved, "Saved initial page.");
+        }
+
+        let pending_pages: Vec<u32> = (2..=checkpoint.total_pages)
+            .filter(|page| !completed.contains(page))
+            .collect();
+
+        self.crawl_pages(
+            query,
+            pending_pages,
+            options.max_concurrency,
+            &context,
+            report,
+        )
+        .await?;
+
+        if report.has_errors() {
+            info!("Leaving the checkpoint open and skipping the removal check because the crawl was incomplete.");
+        } else {
+            self.complete(&context.key, checkpoint.started_at, report)
+                .await?;
+        }
+
+        info!(
+            pages_succeeded = report.pages_succeeded,
+            pages_failed = report.pages_failed,
+            "Collection complete."
+        );
+        Ok(())
+    }
+
+    /// Returns the checkpoint to continue if it can be resumed with the
+    /// current plan, or starts a new one.
+    async fn resume_or_start(
+        &self,
+        key: &str,
+        stored: Option<CrawlCheckpoint>,
+        total_songs: usize,
+        per_page: usize,
+        total_pages: usize,
+    ) -> DbResult<CrawlCheckpoint> {
+        let resumable = match stored {
+            Some(checkpoint) => match checkpoint.can_resume(total_songs as u64, per_page as u32) {
+                Ok(()) => Some(checkpoint),
+                Err(reason) => {
+                    info!(%reason, "Discarding checkpoint.");
+                    None
+                }
+            },
+            None => None,
+        };
+        let Some(mut checkpoint) = resumable else {
+            return self
+                .checkpoints_repo
+                .start(key, total_songs as u64, per_page as u32, total_pages as u32)
+                .await;
+        };
+
+        info!(
+            completed_pages = checkpoint.completed_pages.len(),
+            started_at = %checkpoint.started_at,
+            "Resuming crawl from checkpoint."
+        );
+        if checkpoint.total != total_songs as u64 {
+            info!(
+                previous_total = checkpoint.total,
+                total_songs, "API total drifted since the checkpoint; updating the plan."
+            );
+            checkpoint.total = total_songs as u64;
+            checkpoint.total_pages = total_pages as u32;
+            self.checkpoints_repo
+                .update_plan(key, checkpoint.total, checkpoint.total_pages)
+                .await?;
         }
+        Ok(checkpoint)
+    }
 
-        let total_pages = total_songs.div_ceil(per_page);
+    /// Finishes the checkpoint of a crawl that saved every page, marks the
+    /// songs it did not return as removed and rebuilds their artists.
+    async fn complete(
+        &self,
+        key: &str,
+        started_at: DateTime,
+        report: &mut CollectReport,
+    ) -> DbResult<()> {
+        self.checkpoints_repo.finish(key).await?;
+        let removed_since = DateTime::now();
+        report.songs_removed = self.songs_repo.mark_removed(started_at).await?;
         info!(
-            total_songs,
-            per_page, total_pages, "Created collection plan."
+            songs_removed = report.songs_removed,
+            "Marked songs missing from the crawl as removed."
         );
+        if report.songs_removed > 0 {
+            let artists = self.artists_repo.rebuild_removed(removed_since).await?;
+            info!(artists, "Rebuilt the artists of the removed songs.");
+        }
+        Ok(())
+    }
+
+    /// Crawls only the pages of `query` recorded in `failed_pages`.
+    ///
+    /// Retried pages are fetched long after the snapshot of their crawl was
+    /// taken, so their ranks are recorded as positions but not added to
+    /// `popularity_snapshots`.
+    async fn crawl_failed(
+        &self,
+        query: &CrawlQuery,
+        options: &CollectOptions,
+        run_id: ObjectId,
+        report: &mut CollectReport,
+    ) -> DbResult<()> {
+        let key = query.key();
+        let failed = self.failed_pages_repo.list(&key).await?;
+        if failed.is_empty() {
+            info!("No failed pages to retry.");
+            return Ok(());
+        }
 
-        self.songs_repo
-            .save_many(&first_response.community_songs)
+        info!(pages = failed.len(), "Retrying failed pages.");
+        let pages = failed.into_iter().map(|failed| failed.page).collect();
+        let checkpoint = self.checkpoints_repo.find(&key).await?;
+        let context = CrawlContext {
+            key,
+            run_id,
+            per_page: checkpoint.map(|checkpoint| checkpoint.per_page),
+            snapshot_at: None,
+        };
+        self.crawl_pages(query, pages, options.max_concurrency, &context, report)
             .await?;
-        debug!(page = 1, songs_saved = per_page, "Saved initial page.");
 
-        if total_pages <= 1 {
-            info!("Only one page of data found. Collection complete.");
+        if !report.has_errors() && self.failed_pages_repo.list(&context.key).await?.is_empty() {
+            let checkpoint = self.checkpoints_repo.find(&context.key).await?;
+            if let Some(checkpoint) = checkpoint.filter(CrawlCheckpoint::is_complete) {
+                info!("Every page of the crawl is now saved.");
+                self.complete(&context.key, checkpoint.started_at, report)
+                    .await?;
+            }
+        }
+
+        info!(
+            pages_succeeded = report.pages_succeeded,
+            pages_failed = report.pages_failed,
+            "Retry complete."
+        );
+        Ok(())
+    }
+
+    /// Fetches `pages` of `query` concurrently and saves their songs in batches.
+    ///
+    /// Fetch failures are recorded and counted; only database errors abort the
+    /// crawl, and the report keeps what was saved before them.
+    async fn crawl_pages(
+        &self,
+        query: &CrawlQuery,
+        pages: Vec<u32>,
+        max_concurrency: usize,
+        context: &CrawlContext,
+        report: &mut CollectReport,
+    ) -> DbResult<()> {
+        if pages.is_empty() {
+            debug!("No pages left to fetch.");
             return Ok(());
         }
 
-        let collector = Arc::new(self.clone());
-
-        let final_batch = stream::iter(2..=total_pages as u32)
-            .map(|page| {
-                let collector_clone = Arc::clone(&collector);
-                async move {
-                    debug!(page, "Fetching page.");
-                    let songs = collector_clone
-                        .client
-                        .creators()
-                        .songs()
-                        .page(page)
-                        .send()
-                        .await?
-                        .community_songs;
-                    Ok(songs) as DbResult<Vec<CommunitySong>>
+        let mut outcomes = stream::iter(pages)
+            .map(|page| async move {
+                debug!(page, "Fetching page.");
+                match query.request(&self.client).page(page).send().await {
+                    Ok(response) => PageOutcome::Fetched(page, response.community_songs),
+                    Err(e) => PageOutcome::Failed(page, e.to_string()),
                 }
             })
-            .buffer_unordered(max_concurrency)
-            .try_fold(
-                Vec::with_capacity(BATCH_SIZE),
-                |mut batch, songs_page| async {
-                    batch.extend(songs_page);
-                    if batch.len() >= BATCH_SIZE {
-                        info!(songs_in_batch = batch.len(), "Saving batch to database.");
-                        collector.songs_repo.save_many(&batch).await?;
-                        batch.clear();
-                    }
-                    Ok(batch)
-                },
-            )
-            .await?;
+            .buffer_unordered(max_concurrency);
+
+        let mut batch = PendingBatch::default();
+        while let Some(outcome) = outcomes.next().await {
+            match outcome {
+                PageOutcome::Fetched(page, songs) => {
+                    batch.push_page(context, page, songs);
+                }
+                PageOutcome::Failed(page, error) => {
+                    warn!(page, error, "Failed to fetch page.");
+                    self.failed_pages_repo
+                        .record_failure(&context.key, page, &error)
+                        .await?;
+                    report.pages_failed += 1;
+                    report.errors.push(format!("page {page}: {error}"));
+                }
+            }
+            if batch.songs.len() >= BATCH_SIZE {
+                info!(
+                    songs_in_batch = batch.songs.len(),
+                    "Saving batch to database."
+                );
+                self.flush(context, &mut batch, report).await?;
+            }
+        }
 
-        if !final_batch.is_empty() {
+        if !batch.pages.is_empty() {
             info!(
-                songs_in_batch = final_batch.len(),
+                songs_in_batch = batch.songs.len(),
                 "Saving final batch to database."
             );
-            self.songs_repo.save_many(&final_batch).await?;
+            self.flush(context, &mut batch, report).await?;
         }
 
         Ok(())
     }
+
+    /// Saves a batch of songs and their positions, records its pages in the
+    /// checkpoint and clears them from the failed pages.
+    ///
+    /// Pages with a song that could not be saved are not completed; they are
+    /// recorded in `failed_pages` instead, so that a retry fetches them again.
+    /// Songs that could not be saved are left out of the artists, positions
+    /// and share rates recorded for the batch.
+    async fn flush(
+        &self,
+        context: &CrawlContext,
+        batch: &mut PendingBatch,
+        report: &mut CollectReport,
+    ) -> DbResult<()> {
+        let key = context.key.as_str();
+        let saved = self
+            .songs_repo
+            .save_many(&batch.songs, context.run_id)
+            .await?;
+        for failure in &saved.failures {
+            warn!(
+                song_id = failure.song_id,
+                code = failure.code,
+                error = failure.message,
+                "Failed to save song."
+            );
+        }
+
+        let failed_songs: HashSet<u64> = saved.failures.iter().map(|f| f.song_id).collect();
+        let failed_pages = batch.pages_of(&failed_songs);
+        let saved_pages: Vec<u32> = batch
+            .pages
+            .iter()
+            .copied()
+            .filter(|page| !failed_pages.contains(page))
+            .collect();
+        for &page in &failed_pages {
+            warn!(page, "Recording page with unsaved songs as failed.");
+            self.failed_pages_repo
+                .record_failure(key, page, "songs of the page could not be saved")
+                .await?;
+        }
+        batch.songs.retain(|song| !failed_songs.contains(&song.id));
+        batch
+            .positions
+            .retain(|position| !failed_songs.contains(&position.song_id));
+        report.pages_succeeded += saved_pages.len();
+        report.pages_failed += failed_pages.len();
+        report.saved.add(&saved);
+
+        self.artists_repo.upsert_from_songs(&batch.songs).await?;
+        self.checkpoints_repo
+            .record_flush(key, &saved_pages)
+            .await?;
+        self.failed_pages_repo.resolve(key, &saved_pages).await?;
+        self.positions_repo.record(&batch.positions).await?;
+        self.share_rates_repo
+            .record(&batch.songs, context.run_id)
+            .await?;
+        if let Some(snapshot_at) = context.snapshot_at {
+            self.popularity_repo
+                .record(snapshot_at, &batch.positions)
+                .await?;
+        }
+        batch.songs.clear();
+        batch.positions.clear();
+        batch.pages.clear();
+        Ok(())
+    }
 }
@@ -1,17 +1,71 @@
+mod cli;
 mod db;
 mod ingestion;
+mod reports;
 
-use crate::db::{Db, DbResult};
-use crate::ingestion::SongsCollector;
+use crate::cli::{
+    ArtistsCommand, CalendarBy, Cli, CollaborationsArgs, CollectArgs, Command, GraphFormat,
+    ReportFormat, RunsCommand, ShareReportArgs, ShareReportCommandArgs, SongSearchArgs,
+    SongsCommand,
+};
+use crate::db::{
+    release_date, Bounds, CalendarPeriod, Db, DbError, SongCursor, SongFilter, SongQueryOptions,
+    SongSort,
+};
+use crate::ingestion::{CollectOptions, CrawlQuery, SongsCollector};
+use crate::reports::share_rates::ShareRateReport;
+use clap::Parser;
 use dotenvy::dotenv;
+use futures_util::TryStreamExt;
+use mongodb::bson::{oid::ObjectId, Bson, Document};
 use std::env;
-use std::time::Instant;
-use tracing::{info, Level};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{info, warn, Level};
 use tracing_subscriber::FmtSubscriber;
-use tunecore::TunecoreClient;
+use tunecore::{ResponseCache, Session, TunecoreClient};
+
+/// The errors that end a command.
+#[derive(Error, Debug)]
+enum AppError {
+    /// Represents an error reading or writing the database.
+    #[error(transparent)]
+    Db(#[from] DbError),
+
+    /// Represents a missing or malformed environment variable.
+    #[error("Environment variable error: {0}")]
+    Env(#[from] env::VarError),
+
+    /// Represents an error originating from the Tunecore client library.
+    #[error("Tunecore error: {0}")]
+    Tunecore(#[from] tunecore::Error),
+
+    /// Represents a malformed document ID supplied by the user.
+    #[error("Invalid ID: {0}")]
+    InvalidId(#[from] mongodb::bson::oid::Error),
+
+    /// Represents a lookup for a document that does not exist.
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// Represents an error while sending a webhook notification.
+    #[error("Webhook error: {0}")]
+    Webhook(#[from] reqwest::Error),
+
+    /// Represents an error while writing a report file.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Represents an error while writing command output.
+    #[error("Output error: {0}")]
+    Output(#[from] serde_json::Error),
+}
+
+/// A specialized `Result` type for commands.
+type AppResult<T> = Result<T, AppError>;
 
 #[tokio::main]
-async fn main() -> DbResult<()> {
+async fn main() -> AppResult<()> {
     let subscriber = FmtSubscriber::builder()
         .with_max_level(Level::INFO)
         .finish();
@@ -19,6 +73,7 @@ async fn main() -> DbResult<()> {
         .expect("setting default tracing subscriber failed");
 
     dotenv().ok();
+    let cli = Cli::parse();
 
     info!("Loading configuration...");
 
@@ -27,22 +82,320 @@ async fn main() -> DbResult<()> {
 
     info!("Establishing connections...");
     let db = Db::connect(&db_uri, &db_name).await?;
-    let songs_repo = db.songs();
-    let client = TunecoreClient::new();
-    let collector = SongsCollector::new(&client, &songs_repo);
+    let command = cli
+        .command
+        .unwrap_or(Command::Collect(CollectArgs::default()));
+    // Migrating and checking indexes work on the schema as it is, before it is prepared.
+    if !matches!(command, Command::Migrate { .. } | Command::Indexes { .. }) {
+        db.init().await?;
+    }
+    let mut client = TunecoreClient::new();
+    if let Ok(cache_dir) = env::var("TUNECORE_CACHE_DIR") {
+        let ttl_secs = env::var("TUNECORE_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600);
+        info!(cache_dir, ttl_secs, "Enabling on-disk response cache.");
+        client = client.with_cache(ResponseCache::new(cache_dir, Duration::from_secs(ttl_secs)));
+    }
+    if let Ok(session_file) = env::var("TUNECORE_SESSION_FILE") {
+        info!(session_file, "Restoring TuneCore session.");
+        client = client.with_session(Session::load(&session_file).await?);
+    }
     info!("Setup complete.");
 
-    const MAX_CONCURRENCY: usize = 25;
-    info!(concurrency = MAX_CONCURRENCY, "Starting song collection...");
+    match command {
+        Command::Collect(args) => collect(&client, &db, args).await,
+        Command::Runs(command) => runs(&db, command).await,
+        Command::ShareReport(args) => share_report(&db, args).await,
+        Command::Indexes { json } => {
+            let drift = db.index_drift().await?;
+            println!("{}", reports::indexes::render_drift(&drift, json)?);
+            Ok(())
+        }
+        Command::Artists(command) => artists(&db, command).await,
+        Command::Songs(SongsCommand::Search(args)) => search_songs(&db, args).await,
+        Command::Songs(SongsCommand::Calendar { by, from, to, json }) => {
+            let period = match by {
+                CalendarBy::Week => CalendarPeriod::Week,
+                CalendarBy::Month => CalendarPeriod::Month,
+            };
+            let counts = db
+                .songs()
+                .release_counts(period, Bounds::new(from, to))
+                .await?;
+            println!("{}", reports::releases::render_counts(&counts, json)?);
+            Ok(())
+        }
+        Command::Songs(SongsCommand::Released { from, to, json }) => {
+            let songs = db.songs().released_between(from, to).await?;
+            println!("{}", reports::releases::render_songs(&songs, json)?);
+            Ok(())
+        }
+        Command::Songs(SongsCommand::Upcoming { limit, json }) => {
+            let songs = db
+                .songs()
+                .upcoming_releases(release_date::today(), limit.into())
+                .await?;
+            println!("{}", reports::releases::render_songs(&songs, json)?);
+            Ok(())
+        }
+        Command::Songs(SongsCommand::List {
+            after,
+            sort,
+            desc,
+            limit,
+        }) => {
+            let cursor = match after {
+                Some(token) => SongCursor::parse(&token)?,
+                None => SongCursor::start(SongSort {
+                    field: sort.into(),
+                    descending: desc,
+                }),
+            };
+            let page = db.songs().list_after(&cursor, limit.into()).await?;
+            println!("{}", reports::to_extended_json(&page)?);
+            Ok(())
+        }
+        Command::Collaborations(args) => collaborations(&db, args).await,
+        Command::Migrate {
+            dry_run,
+            status,
+            json,
+        } => migrate(&db, dry_run, status, json).await,
+    }
+}
+
+/// Runs the `artists` command.
+async fn artists(db: &Db, command: ArtistsCommand) -> AppResult<()> {
+    let output = match command {
+        ArtistsCommand::Resolve { json } => {
+            let entities = db.artists().resolve_entities().await?;
+            reports::artists::render_resolution(&entities, json)?
+        }
+        ArtistsCommand::Review {
+            max_confidence,
+            limit,
+            json,
+        } => {
+            let entities = db
+                .artists()
+                .entities_for_review(max_confidence, limit.into())
+                .await?;
+            reports::artists::render_review(&entities, json)?
+        }
+    };
+    println!("{output}");
+    Ok(())
+}
+
+/// Runs the `songs search` command.
+async fn search_songs(db: &Db, args: SongSearchArgs) -> AppResult<()> {
+    let filter = SongFilter {
+        genre_ids: args.genres,
+        mood_ids: args.moods,
+        bpm: Bounds::new(args.bpm_min, args.bpm_max),
+        duration: Bounds::new(args.duration_min, args.duration_max),
+        share_rate: Bounds::new(args.share_min, args.share_max),
+        artist_id: args.artist,
+        street_date: Bounds::new(args.released_from, args.released_to),
+        text: args.text,
+        include_removed: args.include_removed,
+    };
+    let options = SongQueryOptions {
+        sort: SongSort {
+            field: args.sort.into(),
+            descending: args.desc,
+        },
+        fields: args.fields,
+        limit: args.limit.map(i64::from),
+    };
 
-    let start_time = Instant::now();
-    collector.collect_all(MAX_CONCURRENCY).await?;
-    let duration = start_time.elapsed();
+    let songs = db.songs().query::<Document>(&filter, &options).await?;
+    futures_util::pin_mut!(songs);
+    let mut count = 0;
+    while let Some(mut song) = songs.try_next().await? {
+        song.remove("_id");
+        println!("{}", Bson::Document(song).into_relaxed_extjson());
+        count += 1;
+    }
+    info!(count, "Printed matching songs.");
+    Ok(())
+}
+
+/// Runs the `collaborations` command.
+async fn collaborations(db: &Db, args: CollaborationsArgs) -> AppResult<()> {
+    let graph = db.songs().collaboration_graph(args.min_weight).await?;
+    let output = match args.format {
+        GraphFormat::Summary => reports::collaborations::render_summary(&graph, args.top),
+        GraphFormat::Json => reports::collaborations::render_json(&graph)?,
+        GraphFormat::Graphml => reports::collaborations::render_graphml(&graph),
+        GraphFormat::Dot => reports::collaborations::render_dot(&graph),
+    };
+
+    match args.output {
+        Some(path) => {
+            tokio::fs::write(&path, output).await?;
+            info!(
+                path = %path.display(),
+                nodes = graph.metrics.nodes,
+                edges = graph.metrics.edges,
+                "Wrote collaboration graph."
+            );
+        }
+        None => println!("{output}"),
+    }
+    Ok(())
+}
+
+/// Runs the `migrate` command.
+async fn migrate(db: &Db, dry_run: bool, status: bool, json: bool) -> AppResult<()> {
+    let migrator = db.migrations();
+    let output = if status {
+        reports::migrations::render_status(&migrator.status().await?, json)?
+    } else {
+        let outcomes = migrator.run(dry_run).await?;
+        info!(applied = outcomes.len(), dry_run, "Migrations finished.");
+        reports::migrations::render_outcomes(&outcomes, json)?
+    };
+    println!("{output}");
+    Ok(())
+}
+
+/// Runs the `share-report` command.
+async fn share_report(db: &Db, args: ShareReportCommandArgs) -> AppResult<()> {
+    let run_id = match args.run_id {
+        Some(run_id) => ObjectId::parse_str(&run_id)?,
+        None => db
+            .runs()
+            .latest_completed("collect")
+            .await?
+            .map(|run| run.id)
+            .ok_or_else(|| AppError::NotFound("completed collect runs".to_owned()))?,
+    };
+
+    let report = deliver_share_report(db, run_id, &args.share_report).await?;
+    let output = match args.format {
+        ReportFormat::Json => report.to_json()?,
+        ReportFormat::Markdown => report.to_markdown(),
+    };
+    println!("{output}");
+    Ok(())
+}
+
+/// Builds the share rate report of a run and writes and posts it as configured.
+async fn deliver_share_report(
+    db: &Db,
+    run_id: ObjectId,
+    args: &ShareReportArgs,
+) -> AppResult<ShareRateReport> {
+    let report = ShareRateReport::build(db, run_id, args.share_threshold).await?;
+    info!(
+        %run_id,
+        increased = report.increased.len(),
+        decreased = report.decreased.len(),
+        crossed_threshold = report.crossed_threshold.len(),
+        "Built share rate report."
+    );
 
+    if let Some(dir) = &args.share_report_dir {
+        let [json_path, markdown_path] = report.write_to(dir).await?;
+        info!(
+            json = %json_path.display(),
+            markdown = %markdown_path.display(),
+            "Wrote share rate report."
+        );
+    }
+    if let Some(webhook_url) = &args.share_webhook {
+        if report.is_empty() {
+            info!("No share rates changed; skipping webhook.");
+        } else {
+            report.notify(webhook_url).await?;
+            info!("Sent share rate report to webhook.");
+        }
+    }
+
+    Ok(report)
+}
+
+/// Runs the `runs` command.
+async fn runs(db: &Db, command: RunsCommand) -> AppResult<()> {
+    let output = match command {
+        RunsCommand::List { limit, json } => {
+            let runs = db.runs().recent(limit.into()).await?;
+            reports::runs::render_list(runs, json)?
+        }
+        RunsCommand::Show { run_id, json } => {
+            let id = ObjectId::parse_str(&run_id)?;
+            let run = db
+                .runs()
+                .find(id)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("ingestion run {run_id}")))?;
+            reports::runs::render_summary(run, json)?
+        }
+    };
+    println!("{output}");
+    Ok(())
+}
+
+/// Runs the `collect` command.
+async fn collect(client: &TunecoreClient, db: &Db, args: CollectArgs) -> AppResult<()> {
+    // Saving songs in the old schema would mix both forms in the collection.
+    let pending = db.migrations().pending_count().await?;
+    if pending > 0 {
+        return Err(DbError::MigrationsPending(pending).into());
+    }
+    let collector = SongsCollector::new(client, db);
+    let options = CollectOptions {
+        max_concurrency: args.concurrency.get(),
+        fresh_start: args.fresh,
+    };
+    let query = if args.popularity {
+        CrawlQuery::popularity()
+    } else {
+        CrawlQuery::default()
+    };
     info!(
-        duration_secs = duration.as_secs_f64(),
-        "Collection finished successfully."
+        query = query.key(),
+        concurrency = options.max_concurrency,
+        fresh_start = options.fresh_start,
+        retry_failed = args.retry_failed,
+        "Starting song collection..."
     );
 
+    let start_time = Instant::now();
+    let report = if args.retry_failed {
+        collector.retry_failed(&query, &options).await?
+    } else {
+        collector.collect_all(&query, &options).await?
+    };
+    let duration = start_time.elapsed();
+
+    if report.pages_failed > 0 {
+        warn!(
+            duration_secs = duration.as_secs_f64(),
+            pages_succeeded = report.pages_succeeded,
+            pages_failed = report.pages_failed,
+            songs_inserted = report.saved.upserted,
+            songs_updated = report.saved.modified,
+            songs_unchanged = report.saved.unchanged(),
+            "Collection finished with failed pages. Run with --retry-failed to fetch them again."
+        );
+    } else {
+        info!(
+            duration_secs = duration.as_secs_f64(),
+            pages_succeeded = report.pages_succeeded,
+            songs_inserted = report.saved.upserted,
+            songs_updated = report.saved.modified,
+            songs_unchanged = report.saved.unchanged(),
+            "Collection finished successfully."
+        );
+    }
+
+    if let Some(run_id) = report.run_id {
+        deliver_share_report(db, run_id, &args.share_report).await?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,96 @@
+//! Renders resolved artist entities as summaries, tables or JSON.
+
+use super::to_extended_json;
+use crate::db::{ArtistEntity, ArtistLink, MatchReason};
+use crate::AppResult;
+
+/// Renders the outcome of an artist resolution as JSON or as a summary.
+pub fn render_resolution(entities: &[ArtistEntity], json: bool) -> AppResult<String> {
+    if json {
+        return to_extended_json(&entities);
+    }
+
+    let records: usize = entities.iter().map(|entity| entity.members.len()).sum();
+    let merged = entities
+        .iter()
+        .filter(|entity| entity.members.len() > 1)
+        .count();
+    let uncertain = entities
+        .iter()
+        .filter(|entity| entity.members.len() > 1 && entity.confidence < 0.9)
+        .count();
+    let candidates: usize = entities.iter().map(|entity| entity.candidates.len()).sum();
+    Ok(format!(
+        "Resolved {records} artist records into {} artists.\n\
+         {merged} artists combine several records; {uncertain} of them have a confidence below 0.9.\n\
+         {candidates} weaker matches were not merged.\n\
+         Run `extractor artists review` to inspect them.",
+        entities.len()
+    ))
+}
+
+/// Renders clusters for review as JSON or as one block per cluster.
+pub fn render_review(entities: &[ArtistEntity], json: bool) -> AppResult<String> {
+    if json {
+        return to_extended_json(&entities);
+    }
+    if entities.is_empty() {
+        return Ok("No artist clusters to review.".to_owned());
+    }
+
+    let mut out = String::new();
+    for entity in entities {
+        out.push_str(&format!(
+            "{} (entity {}, confidence {:.2}, {} songs)\n",
+            entity.name.ja, entity.id, entity.confidence, entity.song_count
+        ));
+        for member in &entity.members {
+            let common = member
+                .common_artist_id
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| "-".to_owned());
+            out.push_str(&format!(
+                "  {:>10}  {:<32}  {:<32}  common {:<10}  {:>5} songs\n",
+                member.artist_id,
+                member.name.ja,
+                member.name.en.as_deref().unwrap_or("-"),
+                common,
+                member.song_count
+            ));
+        }
+        for link in &entity.links {
+            out.push_str(&format!("  {}\n", link_line(link)));
+        }
+        for link in &entity.candidates {
+            out.push_str(&format!("  candidate {}\n", link_line(link)));
+        }
+        out.push('\n');
+    }
+    Ok(out.trim_end().to_owned())
+}
+
+/// Formats a link as its two records, its evidence and its confidence.
+fn link_line(link: &ArtistLink) -> String {
+    let reasons: Vec<&str> = link
+        .reasons
+        .iter()
+        .map(|reason| reason_label(*reason))
+        .collect();
+    format!(
+        "{} <-> {}: {} ({:.2})",
+        link.from,
+        link.to,
+        reasons.join(", "),
+        link.confidence
+    )
+}
+
+/// Returns the name of a match reason as stored in the database.
+fn reason_label(reason: MatchReason) -> &'static str {
+    match reason {
+        MatchReason::CommonArtist => "common_artist",
+        MatchReason::JaName => "ja_name",
+        MatchReason::EnName => "en_name",
+        MatchReason::KanaName => "kana_name",
+    }
+}
@@ -0,0 +1,170 @@
+//! Exports the artist collaboration graph as GraphML, DOT, JSON or a summary.
+
+use crate::db::{ArtistNode, CollaborationGraph};
+use crate::AppResult;
+
+/// Renders the graph, including its metrics, as pretty-printed JSON.
+pub fn render_json(graph: &CollaborationGraph) -> AppResult<String> {
+    Ok(serde_json::to_string_pretty(graph)?)
+}
+
+/// Renders the graph as a GraphML document.
+pub fn render_graphml(graph: &CollaborationGraph) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+         \x20 <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n\
+         \x20 <key id=\"songs\" for=\"node\" attr.name=\"songs\" attr.type=\"long\"/>\n\
+         \x20 <key id=\"degree\" for=\"node\" attr.name=\"degree\" attr.type=\"long\"/>\n\
+         \x20 <key id=\"component\" for=\"node\" attr.name=\"component\" attr.type=\"long\"/>\n\
+         \x20 <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"long\"/>\n\
+         \x20 <graph id=\"collaborations\" edgedefault=\"undirected\">\n",
+    );
+    for node in &graph.nodes {
+        out.push_str(&format!(
+            "    <node id=\"a{}\">\n\
+             \x20     <data key=\"name\">{}</data>\n\
+             \x20     <data key=\"songs\">{}</data>\n\
+             \x20     <data key=\"degree\">{}</data>\n\
+             \x20     <data key=\"component\">{}</data>\n\
+             \x20   </node>\n",
+            node.artist_id,
+            escape_xml(&node.name),
+            node.songs,
+            node.degree,
+            node.component
+        ));
+    }
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "    <edge source=\"a{}\" target=\"a{}\">\n\
+             \x20     <data key=\"weight\">{}</data>\n\
+             \x20   </edge>\n",
+            edge.source, edge.target, edge.weight
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+/// Renders the graph in the Graphviz DOT language.
+pub fn render_dot(graph: &CollaborationGraph) -> String {
+    let mut out = String::from("graph collaborations {\n");
+    for node in &graph.nodes {
+        out.push_str(&format!(
+            "  {} [label=\"{}\", songs={}, component={}];\n",
+            node.artist_id,
+            escape_dot(&node.name),
+            node.songs,
+            node.component
+        ));
+    }
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "  {} -- {} [weight={}, label=\"{}\"];\n",
+            edge.source, edge.target, edge.weight, edge.weight
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Renders the metrics and the best connected artists as a short summary.
+pub fn render_summary(graph: &CollaborationGraph, top: usize) -> String {
+    let metrics = &graph.metrics;
+    let mut out = format!(
+        "{} artists, {} collaborations, {} components (largest {}), density {:.5}, average degree {:.2}\n",
+        metrics.nodes,
+        metrics.edges,
+        metrics.components,
+        metrics.component_sizes.first().copied().unwrap_or_default(),
+        metrics.density,
+        metrics.average_degree
+    );
+
+    let mut ranked: Vec<&ArtistNode> = graph.nodes.iter().collect();
+    ranked.sort_by(|a, b| {
+        b.degree
+            .cmp(&a.degree)
+            .then(b.weighted_degree.cmp(&a.weighted_degree))
+    });
+    if !ranked.is_empty() {
+        out.push_str(&format!(
+            "\n{:>10}  {:<32}  {:>6}  {:>8}  {:>5}\n",
+            "ARTIST", "NAME", "DEGREE", "WEIGHTED", "SONGS"
+        ));
+    }
+    for node in ranked.into_iter().take(top) {
+        out.push_str(&format!(
+            "{:>10}  {:<32}  {:>6}  {:>8}  {:>5}\n",
+            node.artist_id, node.name, node.degree, node.weighted_degree, node.songs
+        ));
+    }
+    out.trim_end().to_owned()
+}
+
+/// Escapes text for use in XML content and attributes.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Escapes text for use in a quoted DOT string.
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tunecore::models::{Artist, ArtistName};
+
+    fn artist(artist_id: u64, name: &str) -> Artist {
+        Artist {
+            artist_id,
+            name: ArtistName {
+                ja: name.to_owned(),
+                en: None,
+                ja_kana: None,
+            },
+            is_common_artist: false,
+            is_artist_page_available: false,
+            artist_page_path: String::new(),
+            common_artist_id: None,
+        }
+    }
+
+    fn graph(name: &str) -> CollaborationGraph {
+        CollaborationGraph::build(vec![vec![artist(1, name), artist(2, "Plain")]], 1)
+    }
+
+    #[test]
+    fn graphml_escapes_names() {
+        let graphml = render_graphml(&graph("Tom & \"Jerry\" <'s>"));
+
+        assert!(graphml
+            .contains("<data key=\"name\">Tom &amp; &quot;Jerry&quot; &lt;&apos;s&gt;</data>"));
+        assert!(graphml.contains("<edge source=\"a1\" target=\"a2\">"));
+        assert!(graphml.ends_with("</graphml>\n"));
+    }
+
+    #[test]
+    fn dot_escapes_quotes_and_backslashes() {
+        let dot = render_dot(&graph("Say \"hi\" \\o/"));
+
+        assert!(dot.contains("  1 [label=\"Say \\\"hi\\\" \\\\o/\", songs=1, component=0];"));
+        assert!(dot.contains("  1 -- 2 [weight=1, label=\"1\"];"));
+    }
+
+    #[test]
+    fn summary_ranks_the_best_connected_artists() {
+        let summary = render_summary(&graph("First"), 1);
+
+        assert!(summary.starts_with("2 artists, 1 collaborations, 1 components (largest 2)"));
+        assert_eq!(summary.lines().count(), 4);
+        assert!(summary.lines().last().unwrap().contains("First"));
+    }
+}
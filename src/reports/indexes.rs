@@ -0,0 +1,103 @@
+//! Renders index drift as a table or JSON.
+
+use crate::db::{DriftKind, IndexDrift};
+use crate::AppResult;
+
+/// Renders index drift as JSON or as a table with one row per index.
+pub fn render_drift(drift: &[IndexDrift], json: bool) -> AppResult<String> {
+    if json {
+        return Ok(serde_json::to_string_pretty(drift)?);
+    }
+    if drift.is_empty() {
+        return Ok("All declared indexes are in place.".to_owned());
+    }
+
+    let mut out = format!(
+        "{:<22}  {:<22}  {:<10}  {:<32}  {}\n",
+        "COLLECTION", "INDEX", "DRIFT", "EXPECTED", "ACTUAL"
+    );
+    for drift in drift {
+        let keys = |keys: &Option<mongodb::bson::Document>| {
+            keys.as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_else(|| "-".to_owned())
+        };
+        let actual = match &drift.actual_name {
+            Some(name) => format!("{} as {name}", keys(&drift.actual)),
+            None => keys(&drift.actual),
+        };
+        out.push_str(&format!(
+            "{:<22}  {:<22}  {:<10}  {:<32}  {}\n",
+            drift.collection,
+            drift.name,
+            kind_label(&drift.kind),
+            keys(&drift.expected),
+            actual,
+        ));
+    }
+    Ok(out.trim_end().to_owned())
+}
+
+/// Returns the name of a kind of drift.
+fn kind_label(kind: &DriftKind) -> &'static str {
+    match kind {
+        DriftKind::Missing => "missing",
+        DriftKind::Mismatched => "mismatched",
+        DriftKind::Unexpected => "unexpected",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    fn renamed() -> IndexDrift {
+        IndexDrift {
+            collection: "songs".to_owned(),
+            name: "id_unique".to_owned(),
+            kind: DriftKind::Mismatched,
+            expected: Some(doc! { "id": 1 }),
+            actual: Some(doc! { "id": 1 }),
+            actual_name: Some("id_1".to_owned()),
+        }
+    }
+
+    #[test]
+    fn no_drift_says_so() {
+        assert_eq!(
+            render_drift(&[], false).unwrap(),
+            "All declared indexes are in place."
+        );
+    }
+
+    #[test]
+    fn table_shows_the_name_of_a_renamed_index() {
+        let missing = IndexDrift {
+            collection: "songs".to_owned(),
+            name: "street_date".to_owned(),
+            kind: DriftKind::Missing,
+            expected: Some(doc! { "street_date": 1 }),
+            actual: None,
+            actual_name: None,
+        };
+
+        let out = render_drift(&[renamed(), missing], false).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert!(lines[0].starts_with("COLLECTION"));
+        assert!(lines[1].contains("mismatched"));
+        assert!(lines[1].ends_with("{ \"id\": 1 } as id_1"));
+        assert!(lines[2].contains("missing"));
+        assert!(lines[2].ends_with("  -"));
+    }
+
+    #[test]
+    fn json_uses_snake_case_kinds() {
+        let value: serde_json::Value =
+            serde_json::from_str(&render_drift(&[renamed()], true).unwrap()).unwrap();
+
+        assert_eq!(value[0]["kind"], "mismatched");
+        assert_eq!(value[0]["actual_name"], "id_1");
+    }
+}
@@ -0,0 +1,139 @@
+//! Renders the state and outcome of migrations as tables or JSON.
+
+use super::format_time;
+use crate::db::{MigrationOutcome, MigrationStatus};
+use crate::AppResult;
+
+/// Renders the state of every declared migration as JSON or as a table.
+pub fn render_status(statuses: &[MigrationStatus], json: bool) -> AppResult<String> {
+    if json {
+        return Ok(serde_json::to_string_pretty(statuses)?);
+    }
+    if statuses.is_empty() {
+        return Ok("No migrations declared.".to_owned());
+    }
+
+    let mut out = format!(
+        "{:>7}  {:<32}  {:<8}  {:<25}  {:>9}\n",
+        "VERSION", "NAME", "STATE", "APPLIED", "DOCUMENTS"
+    );
+    for status in statuses {
+        let (state, applied_at, documents) = match &status.applied {
+            Some(applied) => (
+                "applied",
+                format_time(applied.applied_at),
+                applied.documents.to_string(),
+            ),
+            None => ("pending", "-".to_owned(), "-".to_owned()),
+        };
+        out.push_str(&format!(
+            "{:>7}  {:<32}  {:<8}  {:<25}  {:>9}\n",
+            status.version, status.name, state, applied_at, documents
+        ));
+    }
+    Ok(out.trim_end().to_owned())
+}
+
+/// Renders the outcome of a migration run as JSON or as a table.
+pub fn render_outcomes(outcomes: &[MigrationOutcome], json: bool) -> AppResult<String> {
+    if json {
+        return Ok(serde_json::to_string_pretty(outcomes)?);
+    }
+    if outcomes.is_empty() {
+        return Ok("No pending migrations.".to_owned());
+    }
+
+    let mut out = format!("{:>7}  {:<32}  {:>9}\n", "VERSION", "NAME", "DOCUMENTS");
+    for outcome in outcomes {
+        let documents = if outcome.dry_run {
+            format!("~{}", outcome.documents)
+        } else {
+            outcome.documents.to_string()
+        };
+        out.push_str(&format!(
+            "{:>7}  {:<32}  {:>9}\n",
+            outcome.version, outcome.name, documents
+        ));
+    }
+    if outcomes.iter().any(|outcome| outcome.dry_run) {
+        out.push_str("Dry run: nothing was written.\n");
+    }
+    Ok(out.trim_end().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{doc, from_document, DateTime};
+
+    fn outcome(version: u32, documents: u64, dry_run: bool) -> MigrationOutcome {
+        MigrationOutcome {
+            version,
+            name: format!("migration_{version}"),
+            documents,
+            dry_run,
+        }
+    }
+
+    #[test]
+    fn status_shows_applied_and_pending_migrations() {
+        let statuses = [
+            MigrationStatus {
+                version: 1,
+                name: "strip_volatile_song_fields".to_owned(),
+                applied: Some(
+                    from_document(doc! {
+                        "_id": 1,
+                        "name": "strip_volatile_song_fields",
+                        "applied_at": DateTime::from_millis(1_700_000_000_000),
+                        "duration_secs": 0.5,
+                        "documents": 42_i64,
+                    })
+                    .unwrap(),
+                ),
+            },
+            MigrationStatus {
+                version: 2,
+                name: "backfill_first_seen_at".to_owned(),
+                applied: None,
+            },
+        ];
+
+        let out = render_status(&statuses, false).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert!(lines[0].trim_start().starts_with("VERSION"));
+        assert!(lines[1].contains("applied   2023-11-14T22:13:20Z"));
+        assert!(lines[1].ends_with(" 42"));
+        assert!(lines[2].contains("pending"));
+        assert!(lines[2].ends_with(" -"));
+    }
+
+    #[test]
+    fn dry_run_counts_are_approximate() {
+        let out = render_outcomes(&[outcome(4, 10, true)], false).unwrap();
+
+        assert!(out.contains(" ~10\n"));
+        assert!(out.ends_with("Dry run: nothing was written."));
+    }
+
+    #[test]
+    fn applied_outcomes_have_exact_counts() {
+        let out = render_outcomes(&[outcome(4, 10, false)], false).unwrap();
+
+        assert!(out.ends_with(" 10"));
+        assert!(!out.contains("Dry run"));
+    }
+
+    #[test]
+    fn nothing_pending_says_so() {
+        assert_eq!(
+            render_outcomes(&[], false).unwrap(),
+            "No pending migrations."
+        );
+        assert_eq!(
+            render_status(&[], false).unwrap(),
+            "No migrations declared."
+        );
+    }
+}
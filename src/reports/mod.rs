@@ -0,0 +1,30 @@
+//! Renders data from the database for the command line.
+
+pub mod artists;
+pub mod collaborations;
+pub mod indexes;
+pub mod migrations;
+pub mod releases;
+pub mod runs;
+pub mod share_rates;
+
+use crate::db::DbError;
+use crate::AppResult;
+use mongodb::bson::{to_bson, DateTime};
+use serde::Serialize;
+
+/// Renders stored data as pretty-printed relaxed extended JSON, so that BSON
+/// dates appear as `{ "$date": "<RFC 3339>" }`.
+pub fn to_extended_json<T: Serialize>(value: &T) -> AppResult<String> {
+    Ok(serde_json::to_string_pretty(
+        &to_bson(value)
+            .map_err(DbError::from)?
+            .into_relaxed_extjson(),
+    )?)
+}
+
+/// Formats a BSON timestamp as an RFC 3339 string.
+fn format_time(time: DateTime) -> String {
+    time.try_to_rfc3339_string()
+        .unwrap_or_else(|_| time.to_string())
+}
@@ -0,0 +1,167 @@
+//! Renders the release calendar as tables or JSON.
+
+use crate::db::{CanonicalSong, ReleaseCount};
+use crate::AppResult;
+use serde::Serialize;
+
+/// A released song as it is shown to users.
+#[derive(Serialize, Debug)]
+pub struct ReleaseView {
+    /// The release date as `YYYY-MM-DD`.
+    pub street_date: String,
+    /// The ID of the song.
+    pub song_id: u64,
+    /// The Japanese title of the song.
+    pub title: String,
+    /// The Japanese name of the primary artist.
+    pub artist: String,
+}
+
+impl From<&CanonicalSong> for ReleaseView {
+    fn from(song: &CanonicalSong) -> Self {
+        Self {
+            street_date: song.street_date.to_string(),
+            song_id: song.id,
+            title: song.song_title.ja.clone(),
+            artist: song.artist_name.ja.clone(),
+        }
+    }
+}
+
+/// A count of releases as it is shown to users.
+#[derive(Serialize, Debug)]
+pub struct ReleaseCountView {
+    /// The first day of the period as `YYYY-MM-DD`.
+    pub period_start: String,
+    /// The number of songs released in the period.
+    pub songs: u64,
+}
+
+/// Renders release counts as JSON or as a table with one row per period.
+pub fn render_counts(counts: &[ReleaseCount], json: bool) -> AppResult<String> {
+    let views: Vec<ReleaseCountView> = counts
+        .iter()
+        .map(|count| ReleaseCountView {
+            period_start: count.period_start.to_string(),
+            songs: count.songs,
+        })
+        .collect();
+    if json {
+        return Ok(serde_json::to_string_pretty(&views)?);
+    }
+    if views.is_empty() {
+        return Ok("No releases in range.".to_owned());
+    }
+
+    let mut out = format!("{:<10}  {:>6}\n", "PERIOD", "SONGS");
+    for view in &views {
+        out.push_str(&format!("{:<10}  {:>6}\n", view.period_start, view.songs));
+    }
+    Ok(out.trim_end().to_owned())
+}
+
+/// Renders released songs as JSON or as a table with one row per song.
+pub fn render_songs(songs: &[CanonicalSong], json: bool) -> AppResult<String> {
+    let views: Vec<ReleaseView> = songs.iter().map(ReleaseView::from).collect();
+    if json {
+        return Ok(serde_json::to_string_pretty(&views)?);
+    }
+    if views.is_empty() {
+        return Ok("No releases found.".to_owned());
+    }
+
+    let mut out = format!(
+        "{:<10}  {:>10}  {:<40}  {}\n",
+        "DATE", "SONG", "TITLE", "ARTIST"
+    );
+    for view in &views {
+        out.push_str(&format!(
+            "{:<10}  {:>10}  {:<40}  {}\n",
+            view.street_date, view.song_id, view.title, view.artist
+        ));
+    }
+    Ok(out.trim_end().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tunecore::models::{ArtistName, ReleaseDate, SongTitle};
+
+    fn song(id: u64, title: &str, street_date: &str) -> CanonicalSong {
+        CanonicalSong {
+            id,
+            audio_url: None,
+            youtube_art_track_url: None,
+            linkcore_url: String::new(),
+            bpm: 120.0,
+            duration: 180.0,
+            genre_id: vec![1],
+            mood_id: 1,
+            jacket_url: String::new(),
+            street_date: street_date.parse().unwrap(),
+            song_title: SongTitle {
+                ja: title.to_owned(),
+                en: None,
+                ja_kana: None,
+            },
+            artist_name: ArtistName {
+                ja: "Artist".to_owned(),
+                en: None,
+                ja_kana: None,
+            },
+            artists: Vec::new(),
+            channel_share_percent_str: "50".to_owned(),
+        }
+    }
+
+    #[test]
+    fn counts_have_one_row_per_period() {
+        let counts = [ReleaseCount {
+            period_start: ReleaseDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            songs: 12,
+        }];
+
+        assert_eq!(
+            render_counts(&counts, false).unwrap(),
+            "PERIOD       SONGS\n2024-03-01      12"
+        );
+        let value: serde_json::Value =
+            serde_json::from_str(&render_counts(&counts, true).unwrap()).unwrap();
+        assert_eq!(value[0]["period_start"], "2024-03-01");
+        assert_eq!(value[0]["songs"], 12);
+    }
+
+    #[test]
+    fn songs_have_one_row_per_song() {
+        let out = render_songs(&[song(7, "歌", "2024-03-01")], false).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("DATE"));
+        assert!(lines[1].starts_with("2024-03-01           7  歌"));
+        assert!(lines[1].ends_with("  Artist"));
+    }
+
+    #[test]
+    fn songs_json_uses_the_view() {
+        let out = render_songs(&[song(7, "歌", "2024-03-01")], true).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!([{
+                "street_date": "2024-03-01",
+                "song_id": 7,
+                "title": "歌",
+                "artist": "Artist",
+            }])
+        );
+    }
+
+    #[test]
+    fn empty_results_say_so() {
+        assert_eq!(render_counts(&[], false).unwrap(), "No releases in range.");
+        assert_eq!(render_songs(&[], false).unwrap(), "No releases found.");
+    }
+}
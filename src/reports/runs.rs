@@ -0,0 +1,223 @@
+//! Renders ingestion runs as tables or JSON.
+
+use super::format_time;
+use crate::db::{IngestionRun, RunStats, RunStatus};
+use crate::AppResult;
+use serde::Serialize;
+
+/// An ingestion run as it is shown to users.
+#[derive(Serialize, Debug)]
+pub struct RunView {
+    /// The run ID as a hex string.
+    pub id: String,
+    /// What the run did.
+    pub mode: String,
+    /// The key identifying the crawled query.
+    pub query: String,
+    /// The state of the run.
+    pub status: RunStatus,
+    /// When the run started, in RFC 3339 format.
+    pub started_at: String,
+    /// When the run finished, in RFC 3339 format.
+    pub finished_at: Option<String>,
+    /// How long the run took, in seconds.
+    pub duration_secs: Option<f64>,
+    /// What the run did.
+    #[serde(flatten)]
+    pub stats: RunStats,
+}
+
+impl From<IngestionRun> for RunView {
+    fn from(run: IngestionRun) -> Self {
+        Self {
+            id: run.id.to_hex(),
+            mode: run.mode,
+            query: run.query,
+            status: run.status,
+            started_at: format_time(run.started_at),
+            finished_at: run.finished_at.map(format_time),
+            duration_secs: run.duration_secs,
+            stats: run.stats,
+        }
+    }
+}
+
+/// Renders a list of runs as JSON or as a table with one row per run.
+pub fn render_list(runs: Vec<IngestionRun>, json: bool) -> AppResult<String> {
+    let views: Vec<RunView> = runs.into_iter().map(RunView::from).collect();
+    if json {
+        return Ok(serde_json::to_string_pretty(&views)?);
+    }
+    if views.is_empty() {
+        return Ok("No ingestion runs recorded.".to_owned());
+    }
+
+    let mut out = format!(
+        "{:<24}  {:<25}  {:<21}  {:<12}  {:>7}  {:>6}  {:>8}  {:>8}  {:>9}  {:>9}\n",
+        "ID",
+        "STARTED",
+        "STATUS",
+        "MODE",
+        "PAGES",
+        "FAILED",
+        "INSERTED",
+        "UPDATED",
+        "UNCHANGED",
+        "DURATION"
+    );
+    for view in &views {
+        out.push_str(&format!(
+            "{:<24}  {:<25}  {:<21}  {:<12}  {:>7}  {:>6}  {:>8}  {:>8}  {:>9}  {:>9}\n",
+            view.id,
+            view.started_at,
+            status_label(view.status),
+            view.mode,
+            view.stats.pages_fetched,
+            view.stats.pages_failed,
+            view.stats.songs_inserted,
+            view.stats.songs_updated,
+            view.stats.songs_unchanged,
+            format_duration(view.duration_secs),
+        ));
+    }
+    Ok(out.trim_end().to_owned())
+}
+
+/// Renders the summary of a single run as JSON or as a two-column table.
+pub fn render_summary(run: IngestionRun, json: bool) -> AppResult<String> {
+    let view = RunView::from(run);
+    if json {
+        return Ok(serde_json::to_string_pretty(&view)?);
+    }
+
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_owned());
+    let rows = [
+        ("Run", view.id.clone()),
+        ("Mode", view.mode.clone()),
+        ("Query", view.query.clone()),
+        ("Status", status_label(view.status).to_owned()),
+        ("Started", view.started_at.clone()),
+        ("Finished", optional(view.finished_at.clone())),
+        ("Duration", format_duration(view.duration_secs)),
+        (
+            "API total",
+            optional(view.stats.api_total.map(|total| total.to_string())),
+        ),
+        ("Pages fetched", view.stats.pages_fetched.to_string()),
+        ("Pages failed", view.stats.pages_failed.to_string()),
+        ("Songs inserted", view.stats.songs_inserted.to_string()),
+        ("Songs updated", view.stats.songs_updated.to_string()),
+        ("Songs unchanged", view.stats.songs_unchanged.to_string()),
+        ("Songs removed", view.stats.songs_removed.to_string()),
+    ];
+
+    let mut out = String::new();
+    for (label, value) in rows {
+        out.push_str(&format!("{label:<16} {value}\n"));
+    }
+    if !view.stats.errors.is_empty() {
+        out.push_str(&format!("Errors ({}):\n", view.stats.errors.len()));
+        for error in &view.stats.errors {
+            out.push_str(&format!("  - {error}\n"));
+        }
+    }
+    Ok(out.trim_end().to_owned())
+}
+
+/// Returns the name of a status as stored in the database.
+fn status_label(status: RunStatus) -> &'static str {
+    match status {
+        RunStatus::Running => "running",
+        RunStatus::Completed => "completed",
+        RunStatus::CompletedWithErrors => "completed_with_errors",
+        RunStatus::Failed => "failed",
+    }
+}
+
+/// Formats a duration in seconds, or `-` for unfinished runs.
+fn format_duration(duration_secs: Option<f64>) -> String {
+    duration_secs
+        .map(|secs| format!("{secs:.1}s"))
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::{oid::ObjectId, DateTime};
+
+    fn run(status: RunStatus, errors: Vec<String>) -> IngestionRun {
+        IngestionRun {
+            id: ObjectId::parse_str("65f000000000000000000001").unwrap(),
+            mode: "collect".to_owned(),
+            query: "songs".to_owned(),
+            status,
+            started_at: DateTime::from_millis(1_700_000_000_000),
+            finished_at: Some(DateTime::from_millis(1_700_000_012_500)),
+            duration_secs: Some(12.5),
+            stats: RunStats {
+                api_total: Some(120),
+                pages_fetched: 2,
+                pages_failed: 1,
+                songs_inserted: 10,
+                songs_updated: 3,
+                songs_unchanged: 87,
+                songs_removed: 0,
+                errors,
+            },
+        }
+    }
+
+    #[test]
+    fn list_has_a_header_and_one_row_per_run() {
+        let out =
+            render_list(vec![run(RunStatus::CompletedWithErrors, Vec::new())], false).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("ID"));
+        assert!(lines[1].starts_with("65f000000000000000000001"));
+        assert!(lines[1].contains("completed_with_errors"));
+        assert!(lines[1].ends_with("12.5s"));
+    }
+
+    #[test]
+    fn empty_list_says_so() {
+        assert_eq!(
+            render_list(Vec::new(), false).unwrap(),
+            "No ingestion runs recorded."
+        );
+    }
+
+    #[test]
+    fn list_json_flattens_the_stats() {
+        let out = render_list(vec![run(RunStatus::Completed, Vec::new())], true).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+
+        assert_eq!(value[0]["id"], "65f000000000000000000001");
+        assert_eq!(value[0]["status"], "completed");
+        assert_eq!(value[0]["pages_fetched"], 2);
+        assert_eq!(value[0]["started_at"], "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn summary_lists_stats_and_errors() {
+        let errors = vec!["page 3: timed out".to_owned()];
+        let out = render_summary(run(RunStatus::Failed, errors), false).unwrap();
+
+        assert!(out.contains("Status           failed"));
+        assert!(out.contains("API total        120"));
+        assert!(out.ends_with("Errors (1):\n  - page 3: timed out"));
+    }
+
+    #[test]
+    fn unfinished_run_shows_placeholders() {
+        let mut running = run(RunStatus::Running, Vec::new());
+        running.finished_at = None;
+        running.duration_secs = None;
+        let out = render_summary(running, false).unwrap();
+
+        assert!(out.contains("Finished         -"));
+        assert!(out.contains("Duration         -"));
+    }
+}
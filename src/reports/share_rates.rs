@@ -0,0 +1,292 @@
+//! Builds reports of the share rate changes observed by an ingestion run,
+//! renders them as JSON or Markdown and sends them to a webhook.
+
+use crate::db::{Db, DbResult, ShareRateChange};
+use crate::AppResult;
+use mongodb::bson::oid::ObjectId;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// How long posting a report to a webhook may take.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A change of one song's share rate.
+#[derive(Serialize, Debug, Clone)]
+pub struct ShareRateMove {
+    /// The ID of the song.
+    pub song_id: u64,
+    /// The Japanese title of the song, if the song is stored.
+    pub title: Option<String>,
+    /// The Japanese name of the primary artist, if the song is stored.
+    pub artist: Option<String>,
+    /// The previous share rate in percent.
+    pub previous: f64,
+    /// The new share rate in percent.
+    pub current: f64,
+    /// The difference between the new and the previous share rate.
+    pub delta: f64,
+}
+
+impl ShareRateMove {
+    /// Returns `true` if the share rate moved from one side of `threshold` to the other.
+    fn crosses(&self, threshold: f64) -> bool {
+        (self.previous < threshold) != (self.current < threshold)
+    }
+}
+
+/// The share rate changes observed by one ingestion run.
+#[derive(Serialize, Debug)]
+pub struct ShareRateReport {
+    /// The run ID as a hex string.
+    pub run_id: String,
+    /// The threshold crossings are reported for, in percent.
+    pub threshold: Option<f64>,
+    /// The songs whose share rate went up, largest increase first.
+    pub increased: Vec<ShareRateMove>,
+    /// The songs whose share rate went down, largest decrease first.
+    pub decreased: Vec<ShareRateMove>,
+    /// The songs whose share rate crossed the threshold in either direction.
+    pub crossed_threshold: Vec<ShareRateMove>,
+}
+
+impl ShareRateReport {
+    /// Builds the report for a run from the recorded share rate changes.
+    ///
+    /// # Arguments
+    /// * `db` - The database to read the changes and songs from.
+    /// * `run_id` - The ingestion run to report on.
+    /// * `threshold` - The share rate to report crossings of, if any.
+    pub async fn build(db: &Db, run_id: ObjectId, threshold: Option<f64>) -> DbResult<Self> {
+        let changes = db.share_rates().changes_in_run(run_id).await?;
+        let ids: Vec<u64> = changes.iter().map(|change| change.song_id).collect();
+        let songs: HashMap<u64, _> = db
+            .songs()
+            .find_many(&ids)
+            .await?
+            .into_iter()
+            .map(|song| (song.id, song))
+            .collect();
+
+        let moves: Vec<ShareRateMove> = changes
+            .into_iter()
+            .filter_map(|change: ShareRateChange| {
+                let previous = change.previous?;
+                let song = songs.get(&change.song_id);
+                Some(ShareRateMove {
+                    song_id: change.song_id,
+                    title: song.map(|song| song.song_title.ja.clone()),
+                    artist: song.map(|song| song.artist_name.ja.clone()),
+                    previous,
+                    current: change.share_rate,
+                    delta: change.share_rate - previous,
+                })
+            })
+            .collect();
+
+        let mut increased: Vec<ShareRateMove> =
+            moves.iter().filter(|m| m.delta > 0.0).cloned().collect();
+        increased.sort_by(|a, b| b.delta.total_cmp(&a.delta));
+        let mut decreased: Vec<ShareRateMove> =
+            moves.iter().filter(|m| m.delta < 0.0).cloned().collect();
+        decreased.sort_by(|a, b| a.delta.total_cmp(&b.delta));
+        let crossed_threshold = match threshold {
+            Some(threshold) => moves.into_iter().filter(|m| m.crosses(threshold)).collect(),
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            run_id: run_id.to_hex(),
+            threshold,
+            increased,
+            decreased,
+            crossed_threshold,
+        })
+    }
+
+    /// Returns `true` if the run did not change any share rate.
+    pub fn is_empty(&self) -> bool {
+        self.increased.is_empty() && self.decreased.is_empty()
+    }
+
+    /// Renders the report as pretty-printed JSON.
+    pub fn to_json(&self) -> AppResult<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Renders the report as a Markdown document.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Share rate changes in run `{}`\n", self.run_id);
+        if self.is_empty() {
+            out.push_str("\nNo share rates changed.\n");
+            return out;
+        }
+
+        if let Some(threshold) = self.threshold {
+            push_section(
+                &mut out,
+                &format!("Crossed {threshold}%"),
+                &self.crossed_threshold,
+            );
+        }
+        push_section(&mut out, "Increased", &self.increased);
+        push_section(&mut out, "Decreased", &self.decreased);
+        out
+    }
+
+    /// Writes the report as `share-rates-<run id>.json` and `.md` into `dir`.
+    ///
+    /// # Returns
+    /// An `AppResult` containing the paths of the written files.
+    pub async fn write_to(&self, dir: &Path) -> AppResult<[PathBuf; 2]> {
+        tokio::fs::create_dir_all(dir).await?;
+        let json_path = dir.join(format!("share-rates-{}.json", self.run_id));
+        let markdown_path = dir.join(format!("share-rates-{}.md", self.run_id));
+        tokio::fs::write(&json_path, self.to_json()?).await?;
+        tokio::fs::write(&markdown_path, self.to_markdown()).await?;
+        Ok([json_path, markdown_path])
+    }
+
+    /// Posts the report to a webhook.
+    ///
+    /// The payload carries the Markdown rendering as `text`, which chat tools
+    /// such as Slack display directly, and the full report as `report`.
+    pub async fn notify(&self, webhook_url: &str) -> AppResult<()> {
+        let payload = json!({ "text": self.to_markdown(), "report": self });
+        webhook_client()?
+            .post(webhook_url)
+            .json(&payload)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Returns the HTTP client shared by all webhook posts.
+fn webhook_client() -> AppResult<&'static reqwest::Client> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()?;
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+/// Appends a Markdown section with a table of share rate changes.
+fn push_section(out: &mut String, title: &str, moves: &[ShareRateMove]) {
+    out.push_str(&format!("\n## {title} ({})\n\n", moves.len()));
+    if moves.is_empty() {
+        out.push_str("None.\n");
+        return;
+    }
+
+    out.push_str("| Song | Title | Artist | Previous | Current | Change |\n");
+    out.push_str("| ---: | --- | --- | ---: | ---: | ---: |\n");
+    for m in moves {
+        out.push_str(&format!(
+            "| {} | {} | {} | {}% | {}% | {:+}% |\n",
+            m.song_id,
+            escape(m.title.as_deref().unwrap_or("-")),
+            escape(m.artist.as_deref().unwrap_or("-")),
+            m.previous,
+            m.current,
+            m.delta,
+        ));
+    }
+}
+
+/// Escapes characters that would break a Markdown table cell.
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn move_of(song_id: u64, previous: f64, current: f64) -> ShareRateMove {
+        ShareRateMove {
+            song_id,
+            title: Some(format!("Song | {song_id}")),
+            artist: None,
+            previous,
+            current,
+            delta: current - previous,
+        }
+    }
+
+    fn report(threshold: Option<f64>) -> ShareRateReport {
+        let up = move_of(1, 40.0, 55.0);
+        let down = move_of(2, 70.0, 60.5);
+        ShareRateReport {
+            run_id: "65f000000000000000000001".to_owned(),
+            threshold,
+            crossed_threshold: vec![up.clone()],
+            increased: vec![up],
+            decreased: vec![down],
+        }
+    }
+
+    #[test]
+    fn crossing_is_detected_in_both_directions() {
+        assert!(move_of(1, 40.0, 55.0).crosses(50.0));
+        assert!(move_of(1, 55.0, 40.0).crosses(50.0));
+        assert!(!move_of(1, 55.0, 60.0).crosses(50.0));
+        assert!(!move_of(1, 30.0, 40.0).crosses(50.0));
+    }
+
+    #[test]
+    fn reaching_the_threshold_counts_as_crossing_it() {
+        assert!(move_of(1, 45.0, 50.0).crosses(50.0));
+        assert!(move_of(1, 50.0, 45.0).crosses(50.0));
+    }
+
+    #[test]
+    fn markdown_lists_each_section() {
+        let markdown = report(Some(50.0)).to_markdown();
+
+        assert!(markdown.starts_with("# Share rate changes in run `65f000000000000000000001`\n"));
+        assert!(markdown.contains("## Crossed 50% (1)"));
+        assert!(markdown.contains("## Increased (1)"));
+        assert!(markdown.contains("| 1 | Song \\| 1 | - | 40% | 55% | +15% |"));
+        assert!(markdown.contains("| 2 | Song \\| 2 | - | 70% | 60.5% | -9.5% |"));
+    }
+
+    #[test]
+    fn markdown_leaves_out_crossings_without_a_threshold() {
+        let mut report = report(None);
+        report.decreased.clear();
+        let markdown = report.to_markdown();
+
+        assert!(!markdown.contains("Crossed"));
+        assert!(markdown.ends_with("## Decreased (0)\n\nNone.\n"));
+    }
+
+    #[test]
+    fn empty_report_says_so() {
+        let mut report = report(Some(50.0));
+        report.increased.clear();
+        report.decreased.clear();
+
+        assert!(report.is_empty());
+        assert!(report
+            .to_markdown()
+            .ends_with("\nNo share rates changed.\n"));
+    }
+
+    #[test]
+    fn json_keeps_the_report_fields() {
+        let value: serde_json::Value =
+            serde_json::from_str(&report(Some(50.0)).to_json().unwrap()).unwrap();
+
+        assert_eq!(value["threshold"], 50.0);
+        assert_eq!(value["increased"][0]["delta"], 15.0);
+        assert_eq!(value["decreased"][0]["song_id"], 2);
+    }
+}
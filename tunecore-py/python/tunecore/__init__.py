@@ -0,0 +1,16 @@
+"""Python bindings for the Tunecore API client.
+
+Example::
+
+    import tunecore
+
+    client = tunecore.Client(cache_dir=".tunecore-cache")
+    page = client.songs().per_page(50).sort("popularity").send()
+
+    for song in client.songs().genre_ids([3]).bpm(120, 140):
+        print(song["id"], song["song_title"]["ja"])
+"""
+
+from tunecore._tunecore import Client, SongsIterator, SongsQuery, TunecoreError
+
+__all__ = ["Client", "SongsIterator", "SongsQuery", "TunecoreError"]
@@ -0,0 +1,439 @@
+//! Python bindings for the Tunecore API client.
+//!
+//! This crate wraps the blocking `tunecore` client in a small Python module,
+//! so notebooks and scripts share the same validated query encoding as the
+//! Rust code. Songs are returned as plain `dict`s mirroring `CommunitySong`.
+//!
+//! Built with maturin; see `pyproject.toml`.
+
+use pyo3::{create_exception, exceptions::PyException, exceptions::PyValueError, prelude::*};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::Duration;
+use tunecore::{
+    blocking::{
+        creators::{CreatorsBuilder, SortBy},
+        TunecoreClient,
+    },
+    models::{CommunityResponse, CommunitySong},
+    CacheMode, ResponseCache,
+};
+
+create_exception!(
+    _tunecore,
+    TunecoreError,
+    PyException,
+    "Raised when a request to the Tunecore API fails."
+);
+
+/// Converts a library error into a Python `TunecoreError`.
+fn to_py_err(err: tunecore::Error) -> PyErr {
+    TunecoreError::new_err(err.to_string())
+}
+
+/// Parses a sort name as accepted from Python.
+fn parse_sort(sort: &str) -> PyResult<SortBy> {
+    match sort {
+        "popularity" => Ok(SortBy::Popularity),
+        "share_rate_desc" => Ok(SortBy::ShareRateDescending),
+        "share_rate_asc" => Ok(SortBy::ShareRateAscending),
+        other => Err(PyValueError::new_err(format!(
+            "unknown sort {other:?}; expected 'popularity', 'share_rate_desc' or 'share_rate_asc'"
+        ))),
+    }
+}
+
+/// Parses a cache mode name as accepted from Python.
+fn parse_cache_mode(mode: &str) -> PyResult<CacheMode> {
+    match mode {
+        "use" => Ok(CacheMode::Use),
+        "refresh" => Ok(CacheMode::Refresh),
+        "bypass" => Ok(CacheMode::Bypass),
+        other => Err(PyValueError::new_err(format!(
+            "unknown cache mode {other:?}; expected 'use', 'refresh' or 'bypass'"
+        ))),
+    }
+}
+
+/// A client for the Tunecore API.
+///
+/// Args:
+///     cache_dir: Optional directory for the on-disk response cache.
+///     cache_ttl_secs: How long cached responses stay fresh, in seconds.
+///     base_url: Optional API host, e.g. for a staging server.
+#[pyclass(name = "Client", module = "tunecore")]
+#[derive(Clone)]
+struct PyClient {
+    inner: TunecoreClient,
+}
+
+#[pymethods]
+impl PyClient {
+    #[new]
+    #[pyo3(signature = (cache_dir=None, cache_ttl_secs=3600, base_url=None))]
+    fn new(
+        cache_dir: Option<PathBuf>,
+        cache_ttl_secs: u64,
+        base_url: Option<&str>,
+    ) -> PyResult<Self> {
+        let mut inner = TunecoreClient::new();
+        if let Some(dir) = cache_dir {
+            inner = inner.with_cache(ResponseCache::new(dir, Duration::from_secs(cache_ttl_secs)));
+        }
+        if let Some(base_url) = base_url {
+            let url = base_url
+                .parse()
+                .map_err(|e| PyValueError::new_err(format!("invalid base_url: {e}")))?;
+            inner = inner.with_base_url(url);
+        }
+        Ok(Self { inner })
+    }
+
+    /// Starts a query against the community songs endpoint.
+    fn songs(&self) -> SongsQuery {
+        SongsQuery {
+            client: self.inner.clone(),
+            params: QueryParams::default(),
+        }
+    }
+}
+
+/// The filters of a songs query, kept owned so the query can be re-sent.
+#[derive(Clone, Default)]
+struct QueryParams {
+    page: Option<u32>,
+    per_page: Option<u32>,
+    artist_ids: Vec<u64>,
+    genre_ids: Vec<u16>,
+    mood_ids: Vec<u16>,
+    vocal: Option<bool>,
+    instrumental: Option<bool>,
+    duration_from: Option<u16>,
+    duration_to: Option<u16>,
+    share_rate_from: Option<u8>,
+    share_rate_to: Option<u8>,
+    bpm_from: Option<u16>,
+    bpm_to: Option<u16>,
+    sort: Option<SortBy>,
+    cache_mode: Option<CacheMode>,
+}
+
+impl QueryParams {
+    /// Applies the parameters to a fresh request builder.
+    fn apply<'a>(&self, mut builder: CreatorsBuilder<'a>) -> CreatorsBuilder<'a> {
+        if let Some(page) = self.page {
+            builder = builder.page(page);
+        }
+        if let Some(per_page) = self.per_page {
+            builder = builder.per_page(per_page);
+        }
+        builder = builder
+            .artist_ids(&self.artist_ids)
+            .genre_ids(&self.genre_ids)
+            .mood_ids(&self.mood_ids);
+        if let Some(vocal) = self.vocal {
+            builder = builder.vocal(vocal);
+        }
+        if let Some(instrumental) = self.instrumental {
+            builder = builder.instrumental(instrumental);
+        }
+        if let Some(duration) = self.duration_from {
+            builder = builder.duration_from(duration);
+        }
+        if let Some(duration) = self.duration_to {
+            builder = builder.duration_to(duration);
+        }
+        if let Some(rate) = self.share_rate_from {
+            builder = builder.share_rate_from(rate);
+        }
+        if let Some(rate) = self.share_rate_to {
+            builder = builder.share_rate_to(rate);
+        }
+        if let Some(bpm) = self.bpm_from {
+            builder = builder.bpm_from(bpm);
+        }
+        if let Some(bpm) = self.bpm_to {
+            builder = builder.bpm_to(bpm);
+        }
+        if let Some(sort) = self.sort {
+            builder = builder.sort(sort);
+        }
+        if let Some(mode) = self.cache_mode {
+            builder = builder.cache_mode(mode);
+        }
+        builder
+    }
+}
+
+/// Sends the query for a single page without holding the GIL.
+fn fetch_page(
+    py: Python<'_>,
+    client: &TunecoreClient,
+    params: &QueryParams,
+) -> PyResult<CommunityResponse> {
+    py.allow_threads(|| params.apply(client.creators().songs()).send())
+        .map_err(to_py_err)
+}
+
+/// A chainable query against the community songs endpoint.
+///
+/// Every setter returns the query itself. Call `send()` for a single page,
+/// or iterate over the query to walk every page starting at `page`.
+#[pyclass(module = "tunecore")]
+#[derive(Clone)]
+struct SongsQuery {
+    client: TunecoreClient,
+    params: QueryParams,
+}
+
+#[pymethods]
+impl SongsQuery {
+    /// Sets the page number for the request.
+    fn page(mut slf: PyRefMut<'_, Self>, page: u32) -> PyRefMut<'_, Self> {
+        slf.params.page = Some(page);
+        slf
+    }
+
+    /// Sets the number of results to return per page.
+    fn per_page(mut slf: PyRefMut<'_, Self>, per_page: u32) -> PyRefMut<'_, Self> {
+        slf.params.per_page = Some(per_page);
+        slf
+    }
+
+    /// Filters songs by one or more artist IDs.
+    fn artist_ids(mut slf: PyRefMut<'_, Self>, ids: Vec<u64>) -> PyRefMut<'_, Self> {
+        slf.params.artist_ids.extend(ids);
+        slf
+    }
+
+    /// Filters songs by one or more genre IDs.
+    fn genre_ids(mut slf: PyRefMut<'_, Self>, ids: Vec<u16>) -> PyRefMut<'_, Self> {
+        slf.params.genre_ids.extend(ids);
+        slf
+    }
+
+    /// Filters songs by one or more mood IDs.
+    fn mood_ids(mut slf: PyRefMut<'_, Self>, ids: Vec<u16>) -> PyRefMut<'_, Self> {
+        slf.params.mood_ids.extend(ids);
+        slf
+    }
+
+    /// Filters for songs that have vocals (`True`) or not (`False`).
+    fn vocal(mut slf: PyRefMut<'_, Self>, vocal: bool) -> PyRefMut<'_, Self> {
+        slf.params.vocal = Some(vocal);
+        slf
+    }
+
+    /// Filters for songs that are instrumental (`True`) or not (`False`).
+    fn instrumental(mut slf: PyRefMut<'_, Self>, instrumental: bool) -> PyRefMut<'_, Self> {
+        slf.params.instrumental = Some(instrumental);
+        slf
+    }
+
+    /// Sets the duration range for songs (in seconds). Either bound may be `None`.
+    #[pyo3(signature = (start=None, end=None))]
+    fn duration(
+        mut slf: PyRefMut<'_, Self>,
+        start: Option<u16>,
+        end: Option<u16>,
+    ) -> PyRefMut<'_, Self> {
+        slf.params.duration_from = start;
+        slf.params.duration_to = end;
+        slf
+    }
+
+    /// Sets the revenue share rate range for songs. Either bound may be `None`.
+    #[pyo3(signature = (start=None, end=None))]
+    fn share_rate(
+        mut slf: PyRefMut<'_, Self>,
+        start: Option<u8>,
+        end: Option<u8>,
+    ) -> PyRefMut<'_, Self> {
+        slf.params.share_rate_from = start;
+        slf.params.share_rate_to = end;
+        slf
+    }
+
+    /// Sets the BPM range for songs. Either bound may be `None`.
+    #[pyo3(signature = (start=None, end=None))]
+    fn bpm(
+        mut slf: PyRefMut<'_, Self>,
+        start: Option<u16>,
+        end: Option<u16>,
+    ) -> PyRefMut<'_, Self> {
+        slf.params.bpm_from = start;
+        slf.params.bpm_to = end;
+        slf
+    }
+
+    /// Sets the sorting order: 'popularity', 'share_rate_desc' or 'share_rate_asc'.
+    fn sort<'py>(mut slf: PyRefMut<'py, Self>, sort: &str) -> PyResult<PyRefMut<'py, Self>> {
+        slf.params.sort = Some(parse_sort(sort)?);
+        Ok(slf)
+    }
+
+    /// Sets how the request uses the client's cache: 'use', 'refresh' or 'bypass'.
+    fn cache_mode<'py>(mut slf: PyRefMut<'py, Self>, mode: &str) -> PyResult<PyRefMut<'py, Self>> {
+        slf.params.cache_mode = Some(parse_cache_mode(mode)?);
+        Ok(slf)
+    }
+
+    /// Sends the query and returns the response as a dict with
+    /// `community_songs` and `total` keys.
+    fn send<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let response = fetch_page(py, &self.client, &self.params)?;
+        Ok(pythonize::pythonize(py, &response)?)
+    }
+
+    /// Returns an iterator over the songs of every page, fetching lazily.
+    fn __iter__(&self) -> SongsIterator {
+        SongsIterator {
+            client: self.client.clone(),
+            progress: PageProgress::new(self.params.page.unwrap_or(1), self.params.per_page),
+            params: self.params.clone(),
+            buffer: VecDeque::new(),
+        }
+    }
+}
+
+/// Tracks how far an iteration over the pages of a query has got.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PageProgress {
+    /// The page to fetch next.
+    next_page: u32,
+    /// The page size requested, if any; otherwise the size of the first page is used.
+    per_page: Option<u32>,
+    /// The number of songs on the fetched pages and the pages skipped before
+    /// them, or `None` before the first page.
+    seen: Option<usize>,
+    /// Whether the last page has been fetched.
+    exhausted: bool,
+}
+
+impl PageProgress {
+    /// Starts an iteration at `first_page`.
+    fn new(first_page: u32, per_page: Option<u32>) -> Self {
+        Self {
+            next_page: first_page,
+            per_page,
+            seen: None,
+            exhausted: false,
+        }
+    }
+
+    /// Records a fetched page holding `len` songs of the `total` matching the query.
+    ///
+    /// Counting the songs actually returned, rather than multiplying the page
+    /// number by the length of the current page, keeps a short last page from
+    /// ending the iteration early or fetching an extra empty page.
+    fn record(&mut self, len: usize, total: usize) {
+        let skipped = || {
+            let page_size = self.per_page.map_or(len, |per_page| per_page as usize);
+            (self.next_page as usize).saturating_sub(1) * page_size
+        };
+        let seen = self.seen.unwrap_or_else(skipped) + len;
+        self.seen = Some(seen);
+        self.exhausted = len == 0 || seen >= total;
+        self.next_page += 1;
+    }
+}
+
+/// Iterates over the songs of a query across all pages.
+#[pyclass(module = "tunecore")]
+struct SongsIterator {
+    client: TunecoreClient,
+    params: QueryParams,
+    progress: PageProgress,
+    buffer: VecDeque<CommunitySong>,
+}
+
+#[pymethods]
+impl SongsIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        if self.buffer.is_empty() && !self.progress.exhausted {
+            let mut params = self.params.clone();
+            params.page = Some(self.progress.next_page);
+            let response = fetch_page(py, &self.client, &params)?;
+
+            self.progress
+                .record(response.community_songs.len(), response.total);
+            self.buffer.extend(response.community_songs);
+        }
+
+        match self.buffer.pop_front() {
+            Some(song) => Ok(Some(pythonize::pythonize(py, &song)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The native extension module, re-exported by the `tunecore` Python package.
+#[pymodule]
+fn _tunecore(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyClient>()?;
+    m.add_class::<SongsQuery>()?;
+    m.add_class::<SongsIterator>()?;
+    m.add("TunecoreError", m.py().get_type::<TunecoreError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PageProgress;
+
+    /// Records pages of the given lengths and returns whether each one ended the iteration.
+    fn exhausted_after(mut progress: PageProgress, pages: &[usize], total: usize) -> Vec<bool> {
+        pages
+            .iter()
+            .map(|&len| {
+                progress.record(len, total);
+                progress.exhausted
+            })
+            .collect()
+    }
+
+    #[test]
+    fn short_last_page_ends_the_iteration() {
+        let progress = PageProgress::new(1, Some(10));
+        assert_eq!(
+            exhausted_after(progress, &[10, 10, 5], 25),
+            [false, false, true]
+        );
+    }
+
+    #[test]
+    fn full_last_page_ends_the_iteration() {
+        let progress = PageProgress::new(1, None);
+        assert_eq!(exhausted_after(progress, &[10, 10], 20), [false, true]);
+    }
+
+    #[test]
+    fn pages_before_the_first_one_fetched_count_as_seen() {
+        let progress = PageProgress::new(3, Some(10));
+        assert_eq!(exhausted_after(progress, &[10, 5], 35), [false, true]);
+    }
+
+    #[test]
+    fn starting_on_the_short_last_page_ends_the_iteration() {
+        let progress = PageProgress::new(3, Some(10));
+        assert_eq!(exhausted_after(progress, &[5], 25), [true]);
+    }
+
+    #[test]
+    fn empty_page_ends_the_iteration() {
+        let progress = PageProgress::new(1, Some(10));
+        assert_eq!(exhausted_after(progress, &[10, 0], 30), [false, true]);
+    }
+
+    #[test]
+    fn pages_advance_one_at_a_time() {
+        let mut progress = PageProgress::new(1, None);
+        progress.record(10, 100);
+        assert_eq!(progress.next_page, 2);
+        assert_eq!(progress.seen, Some(10));
+    }
+}
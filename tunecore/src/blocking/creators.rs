@@ -0,0 +1,136 @@
+//! Blocking handlers for the creators API endpoints.
+
+pub use crate::creators::SortBy;
+
+use crate::{cache::CacheMode, creators, error::Error, models::CommunityResponse};
+use tokio::runtime::Runtime;
+
+/// A blocking handler for endpoints related to creators.
+pub struct CreatorsEndpoint<'a> {
+    inner: creators::CreatorsEndpoint<'a>,
+    runtime: &'a Runtime,
+}
+
+impl<'a> CreatorsEndpoint<'a> {
+    /// Creates a new instance of the endpoint handler. (Internal use only)
+    pub(crate) fn new(inner: creators::CreatorsEndpoint<'a>, runtime: &'a Runtime) -> Self {
+        Self { inner, runtime }
+    }
+
+    /// Builds a request to fetch community songs.
+    ///
+    /// Returns a `CreatorsBuilder` to set filters and execute the request.
+    pub fn songs(&self) -> CreatorsBuilder<'a> {
+        CreatorsBuilder {
+            inner: self.inner.songs(),
+            runtime: self.runtime,
+        }
+    }
+}
+
+/// Builds and executes a blocking request to the community songs endpoint.
+///
+/// See [`crate::creators::CreatorsBuilder`] for details on each filter.
+pub struct CreatorsBuilder<'a> {
+    inner: creators::CreatorsBuilder<'a>,
+    runtime: &'a Runtime,
+}
+
+impl CreatorsBuilder<'_> {
+    // --- Builder Methods ---
+
+    /// Sets the page number for the request.
+    pub fn page(mut self, page: u32) -> Self {
+        self.inner = self.inner.page(page);
+        self
+    }
+
+    /// Sets the number of results to return per page.
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.inner = self.inner.per_page(per_page);
+        self
+    }
+
+    /// Filters songs by one or more artist IDs.
+    pub fn artist_ids(mut self, ids: &[u64]) -> Self {
+        self.inner = self.inner.artist_ids(ids);
+        self
+    }
+
+    /// Filters songs by one or more genre IDs.
+    pub fn genre_ids(mut self, ids: &[u16]) -> Self {
+        self.inner = self.inner.genre_ids(ids);
+        self
+    }
+
+    /// Filters songs by one or more mood IDs.
+    pub fn mood_ids(mut self, ids: &[u16]) -> Self {
+        self.inner = self.inner.mood_ids(ids);
+        self
+    }
+
+    /// Filters for songs that have vocals (`true`) or not (`false`).
+    pub fn vocal(mut self, vocal: bool) -> Self {
+        self.inner = self.inner.vocal(vocal);
+        self
+    }
+
+    /// Filters for songs that are instrumental (`true`) or not (`false`).
+    pub fn instrumental(mut self, instrumental: bool) -> Self {
+        self.inner = self.inner.instrumental(instrumental);
+        self
+    }
+
+    /// Sets the minimum duration for songs (in seconds).
+    pub fn duration_from(mut self, duration: u16) -> Self {
+        self.inner = self.inner.duration_from(duration);
+        self
+    }
+
+    /// Sets the maximum duration for songs (in seconds).
+    pub fn duration_to(mut self, duration: u16) -> Self {
+        self.inner = self.inner.duration_to(duration);
+        self
+    }
+
+    /// Sets the maximum revenue share rate for songs.
+    pub fn share_rate_to(mut self, rate: u8) -> Self {
+        self.inner = self.inner.share_rate_to(rate);
+        self
+    }
+
+    /// Sets the minimum revenue share rate for songs.
+    pub fn share_rate_from(mut self, rate: u8) -> Self {
+        self.inner = self.inner.share_rate_from(rate);
+        self
+    }
+
+    /// Sets the sorting order for the results.
+    pub fn sort(mut self, order: SortBy) -> Self {
+        self.inner = self.inner.sort(order);
+        self
+    }
+
+    /// Sets the minimum beats per minute (BPM) for songs.
+    pub fn bpm_from(mut self, bpm: u16) -> Self {
+        self.inner = self.inner.bpm_from(bpm);
+        self
+    }
+
+    /// Sets the maximum beats per minute (BPM) for songs.
+    pub fn bpm_to(mut self, bpm: u16) -> Self {
+        self.inner = self.inner.bpm_to(bpm);
+        self
+    }
+
+    /// Sets how this request interacts with the client's response cache.
+    pub fn cache_mode(mut self, mode: CacheMode) -> Self {
+        self.inner = self.inner.cache_mode(mode);
+        self
+    }
+
+    /// Executes the request against the API, blocking until it completes.
+    pub fn send(self) -> Result<CommunityResponse, Error> {
+        self.runtime.block_on(self.inner.send())
+    }
+}
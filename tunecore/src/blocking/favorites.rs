@@ -0,0 +1,60 @@
+//! Blocking handlers for the favorites endpoints.
+
+use crate::{error::Error, favorites, models::CommunityResponse};
+use tokio::runtime::Runtime;
+
+/// A blocking handler for endpoints related to favorite songs.
+pub struct FavoritesEndpoint<'a> {
+    inner: favorites::FavoritesEndpoint<'a>,
+    runtime: &'a Runtime,
+}
+
+impl<'a> FavoritesEndpoint<'a> {
+    /// Creates a new instance of the endpoint handler. (Internal use only)
+    pub(crate) fn new(inner: favorites::FavoritesEndpoint<'a>, runtime: &'a Runtime) -> Self {
+        Self { inner, runtime }
+    }
+
+    /// Builds a request to list the user's favorite songs.
+    pub fn list(&self) -> FavoritesBuilder<'a> {
+        FavoritesBuilder {
+            inner: self.inner.list(),
+            runtime: self.runtime,
+        }
+    }
+
+    /// Adds the song with the given ID to the user's favorites.
+    pub fn add(&self, song_id: u64) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.add(song_id))
+    }
+
+    /// Removes the song with the given ID from the user's favorites.
+    pub fn remove(&self, song_id: u64) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.remove(song_id))
+    }
+}
+
+/// Builds and executes a blocking request listing the user's favorite songs.
+pub struct FavoritesBuilder<'a> {
+    inner: favorites::FavoritesBuilder<'a>,
+    runtime: &'a Runtime,
+}
+
+impl FavoritesBuilder<'_> {
+    /// Sets the page number for the request.
+    pub fn page(mut self, page: u32) -> Self {
+        self.inner = self.inner.page(page);
+        self
+    }
+
+    /// Sets the number of results to return per page.
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.inner = self.inner.per_page(per_page);
+        self
+    }
+
+    /// Executes the request against the API, blocking until it completes.
+    pub fn send(self) -> Result<CommunityResponse, Error> {
+        self.runtime.block_on(self.inner.send())
+    }
+}
@@ -0,0 +1,154 @@
+//! A blocking client for the Tunecore API.
+//!
+//! This module mirrors the asynchronous API of the crate root, modeled on
+//! `reqwest::blocking`: every request is driven to completion on a small
+//! runtime owned by the client, so no async runtime is needed by the caller.
+//!
+//! The blocking client must not be used from within an async runtime, as
+//! blocking there would stall the executor.
+//!
+//! Requires the `blocking` feature.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! # use tunecore::{blocking::TunecoreClient, creators::SortBy, Error};
+//! # fn main() -> Result<(), Error> {
+//! let client = TunecoreClient::new();
+//!
+//! let response = client
+//!     .creators()
+//!     .songs()
+//!     .per_page(10)
+//!     .sort(SortBy::Popularity)
+//!     .send()?;
+//!
+//! println!("Found {} songs.", response.community_songs.len());
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod creators;
+pub mod favorites;
+
+use crate::{error::Error, Credentials, ResponseCache, Session};
+use creators::CreatorsEndpoint;
+use favorites::FavoritesEndpoint;
+use reqwest::Client;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::runtime::{Builder, Runtime};
+use url::Url;
+
+/// The blocking counterpart of [`crate::TunecoreClient`].
+///
+/// Cloning the client is cheap; clones share the same runtime, cache and session.
+#[derive(Debug, Clone)]
+pub struct TunecoreClient {
+    inner: crate::TunecoreClient,
+    runtime: Arc<Runtime>,
+}
+
+impl TunecoreClient {
+    /// Creates a new `TunecoreClient` with a default `reqwest::Client`.
+    ///
+    /// # Panics
+    /// Panics if the internal runtime cannot be created.
+    pub fn new() -> Self {
+        Self::from_async(crate::TunecoreClient::new())
+    }
+
+    /// Creates a new `TunecoreClient` with a user-provided `reqwest::Client`.
+    ///
+    /// # Panics
+    /// Panics if the internal runtime cannot be created.
+    pub fn with_client(client: Client) -> Self {
+        Self::from_async(crate::TunecoreClient::with_client(client))
+    }
+
+    /// Wraps an existing asynchronous client.
+    ///
+    /// # Panics
+    /// Panics if the internal runtime cannot be created.
+    pub fn from_async(inner: crate::TunecoreClient) -> Self {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build the blocking client runtime");
+
+        Self {
+            inner,
+            runtime: Arc::new(runtime),
+        }
+    }
+
+    /// Sends all requests to `base_url` instead of the public TuneCore host.
+    pub fn with_base_url(self, base_url: Url) -> Self {
+        self.map(|inner| inner.with_base_url(base_url))
+    }
+
+    /// Enables the on-disk response cache for every request made by this client.
+    pub fn with_cache(self, cache: ResponseCache) -> Self {
+        self.map(|inner| inner.with_cache(cache))
+    }
+
+    /// Uses an existing session.
+    pub fn with_session(self, session: Session) -> Self {
+        self.map(|inner| inner.with_session(session))
+    }
+
+    /// Restores a session previously written with [`TunecoreClient::save_session`].
+    pub fn with_session_file(self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let session = self.runtime.block_on(Session::load(path))?;
+        Ok(self.with_session(session))
+    }
+
+    /// Returns the session holding this client's cookies.
+    pub fn session(&self) -> &Session {
+        self.inner.session()
+    }
+
+    /// Writes the client's session cookies to `path`.
+    pub fn save_session(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.session().save(path))
+    }
+
+    /// Logs in to TuneCore with the given credentials.
+    pub fn login(&self, credentials: &Credentials) -> Result<(), Error> {
+        self.runtime.block_on(self.inner.login(credentials))
+    }
+
+    /// Returns a handler for the "creators" API endpoints.
+    pub fn creators(&self) -> CreatorsEndpoint<'_> {
+        CreatorsEndpoint::new(self.inner.creators(), &self.runtime)
+    }
+
+    /// Returns a handler for the favorites endpoints.
+    pub fn favorites(&self) -> FavoritesEndpoint<'_> {
+        FavoritesEndpoint::new(self.inner.favorites(), &self.runtime)
+    }
+
+    /// Returns the underlying asynchronous client.
+    pub fn as_async(&self) -> &crate::TunecoreClient {
+        &self.inner
+    }
+
+    // --- Private Helper Methods ---
+
+    /// Applies a configuration change to the inner client.
+    fn map(self, f: impl FnOnce(crate::TunecoreClient) -> crate::TunecoreClient) -> Self {
+        Self {
+            inner: f(self.inner),
+            runtime: self.runtime,
+        }
+    }
+}
+
+impl Default for TunecoreClient {
+    /// Creates a default `TunecoreClient`.
+    ///
+    /// This is equivalent to calling `TunecoreClient::new()`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,171 @@
+//! An optional on-disk cache for API responses.
+//!
+//! Responses are keyed by their full request URL and the session cookies
+//! sent with them, and stored as small JSON files inside a user-provided
+//! directory. Responses personalised for a logged-in session, such as the
+//! `is_favorite` flag on songs, are therefore never served to another
+//! session. Entries younger than the configured TTL are served without
+//! touching the network; stale entries are revalidated with `If-None-Match` /
+//! `If-Modified-Since` when the server supplied an `ETag` or `Last-Modified`
+//! header.
+
+use crate::error::Error;
+use reqwest::header::HeaderValue;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+/// Controls how a single request interacts with the response cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Serves fresh entries from the cache and revalidates stale ones.
+    #[default]
+    Use,
+    /// Always contacts the server (revalidating if possible) and updates the cache.
+    Refresh,
+    /// Neither reads from nor writes to the cache.
+    Bypass,
+}
+
+/// A directory-backed cache of API responses with a fixed time-to-live.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use tunecore::{cache::ResponseCache, TunecoreClient};
+/// let cache = ResponseCache::new(".tunecore-cache", Duration::from_secs(3600));
+/// let client = TunecoreClient::new().with_cache(cache);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+/// A single cached response as it is stored on disk.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub(crate) struct CacheEntry {
+    /// The full request URL, kept for debugging purposes.
+    pub url: String,
+    /// The `ETag` header returned by the server, if any.
+    pub etag: Option<String>,
+    /// The `Last-Modified` header returned by the server, if any.
+    pub last_modified: Option<String>,
+    /// The time the entry was stored or last revalidated, in seconds since the Unix epoch.
+    pub stored_at: u64,
+    /// The raw response body.
+    pub body: String,
+}
+
+impl CacheEntry {
+    /// Creates a new entry stamped with the current time.
+    pub(crate) fn new(
+        url: &Url,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        body: String,
+    ) -> Self {
+        Self {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            stored_at: now_secs(),
+            body,
+        }
+    }
+
+    /// Returns `true` if the server gave us a validator for conditional requests.
+    pub(crate) fn can_revalidate(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+
+    /// Marks the entry as freshly validated.
+    pub(crate) fn touch(&mut self) {
+        self.stored_at = now_secs();
+    }
+}
+
+impl ResponseCache {
+    /// Creates a new cache rooted at `dir` whose entries stay fresh for `ttl`.
+    ///
+    /// The directory is created lazily on the first write.
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl,
+        }
+    }
+
+    /// Returns the directory the cache is stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the time-to-live of cache entries.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Removes every entry from the cache.
+    pub async fn clear(&self) -> Result<(), Error> {
+        match tokio::fs::remove_dir_all(&self.dir).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Error::from(e)),
+        }
+    }
+
+    /// Returns `true` if the entry is younger than the cache TTL.
+    pub(crate) fn is_fresh(&self, entry: &CacheEntry) -> bool {
+        now_secs().saturating_sub(entry.stored_at) < self.ttl.as_secs()
+    }
+
+    /// Loads the entry for `url` requested with the `cookies` header, if any.
+    ///
+    /// Missing or unreadable entries are treated as cache misses.
+    pub(crate) async fn load(
+        &self,
+        url: &Url,
+        cookies: Option<&HeaderValue>,
+    ) -> Option<CacheEntry> {
+        let bytes = tokio::fs::read(self.entry_path(url, cookies)).await.ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Writes the entry for `url` requested with the `cookies` header, if any,
+    /// replacing any existing one.
+    pub(crate) async fn store(
+        &self,
+        url: &Url,
+        cookies: Option<&HeaderValue>,
+        entry: &CacheEntry,
+    ) -> Result<(), Error> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let bytes = serde_json::to_vec(entry)?;
+        tokio::fs::write(self.entry_path(url, cookies), bytes).await?;
+        Ok(())
+    }
+
+    /// Maps a URL and the session cookies sent with it to a stable file name
+    /// inside the cache directory.
+    fn entry_path(&self, url: &Url, cookies: Option<&HeaderValue>) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(url.as_str().as_bytes());
+        if let Some(cookies) = cookies {
+            hasher.update(b"\n");
+            hasher.update(cookies.as_bytes());
+        }
+        self.dir.join(format!("{:x}.json", hasher.finalize()))
+    }
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
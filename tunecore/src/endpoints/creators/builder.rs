@@ -1,12 +1,14 @@
 use super::types::SortBy;
-use crate::{error::Error, models::CommunityResponse};
-use reqwest::Client;
-use url::{Url, UrlQuery};
+use crate::{
+    cache::CacheMode, endpoints::Endpoint, error::Error, models::CommunityResponse, TunecoreClient,
+};
+use serde::Serialize;
+use std::borrow::Cow;
 
 // --- Constants ---
 
-/// The base URL for the Tunecore community API endpoint.
-const CREATORS_API_BASE: &str = "https://www.tunecore.co.jp/api/v2/community";
+/// The base path for the Tunecore community API endpoint.
+const CREATORS_API_BASE: &str = "api/v2/community";
 /// The default page number to use for paginated requests.
 const DEFAULT_PAGE: u32 = 1;
 /// The default number of items to request per page.
@@ -40,8 +42,10 @@ const DEFAULT_PER_PAGE: u32 = 100;
 /// # Ok(())
 /// # }
 /// ```
+#[derive(Serialize)]
 pub struct CreatorsBuilder<'a> {
-    client: &'a Client,
+    #[serde(skip)]
+    client: &'a TunecoreClient,
     page: u32,
     per_page: u32,
     artist_ids: Vec<u64>,
@@ -56,12 +60,14 @@ pub struct CreatorsBuilder<'a> {
     bpm_from: Option<u16>,
     bpm_to: Option<u16>,
     sort: Option<SortBy>,
+    #[serde(skip)]
+    cache_mode: CacheMode,
 }
 
 impl<'a> CreatorsBuilder<'a> {
     /// Creates a new `CreatorsBuilder` with default values.
     /// This is intended for internal use by the `CreatorsEndpoint`.
-    pub(crate) fn new(client: &'a Client) -> Self {
+    pub(crate) fn new(client: &'a TunecoreClient) -> Self {
         Self {
             client,
             page: DEFAULT_PAGE,
@@ -78,6 +84,7 @@ impl<'a> CreatorsBuilder<'a> {
             bpm_from: None,
             bpm_to: None,
             sort: None,
+            cache_mode: CacheMode::default(),
         }
     }
 
@@ -167,79 +174,31 @@ impl<'a> CreatorsBuilder<'a> {
         self
     }
 
+    /// Sets how this request interacts with the client's response cache.
+    ///
+    /// Has no effect if the client was created without a cache.
+    pub fn cache_mode(mut self, mode: CacheMode) -> Self {
+        self.cache_mode = mode;
+        self
+    }
+
     /// Executes the request against the API.
     ///
     /// This consumes the builder and returns a `Result` containing either
     /// a `CommunityResponse` on success or an `Error` on failure.
     pub async fn send(self) -> Result<CommunityResponse, Error> {
-        let url_path = format!("{CREATORS_API_BASE}/songs");
-        let mut url = Url::parse(&url_path)?;
-
-        self.build_url(&mut url);
-
-        self.client
-            .get(url)
-            .send()
-            .await?
-            .json::<CommunityResponse>()
-            .await
-            .map_err(Error::from)
-    }
-
-    // --- Private Helper Methods ---
-
-    /// Assembles the final URL with all specified query parameters.
-    fn build_url(&self, url: &mut Url) {
-        let mut query = url.query_pairs_mut();
-
-        query.append_pair("page", &self.page.to_string());
-        query.append_pair("per_page", &self.per_page.to_string());
-
-        self.append_vec_param(&mut query, "artist_ids", &self.artist_ids);
-        self.append_vec_param(&mut query, "genre_ids", &self.genre_ids);
-        self.append_vec_param(&mut query, "mood_ids", &self.mood_ids);
-
-        self.append_optional_param(&mut query, "vocal", self.vocal);
-        self.append_optional_param(&mut query, "instrumental", self.instrumental);
-        self.append_optional_param(&mut query, "duration_from", self.duration_from);
-        self.append_optional_param(&mut query, "duration_to", self.duration_to);
-        self.append_optional_param(&mut query, "share_rate_from", self.share_rate_from);
-        self.append_optional_param(&mut query, "share_rate_to", self.share_rate_to);
-        self.append_optional_param(&mut query, "bpm_from", self.bpm_from);
-        self.append_optional_param(&mut query, "bpm_to", self.bpm_to);
-
-        if let Some(sort_param) = self.sort {
-            let sort_str = match sort_param {
-                SortBy::Popularity => "popular_rank:desc",
-                SortBy::ShareRateDescending => "share_rate:desc",
-                SortBy::ShareRateAscending => "share_rate:asc",
-            };
-            query.append_pair("sort", sort_str);
-        }
+        self.client.send(&self).await
     }
+}
 
-    /// A generic helper to append an optional parameter to the query string.
-    /// If the value is `None`, nothing is appended.
-    fn append_optional_param<T: ToString>(
-        &self,
-        query: &mut url::form_urlencoded::Serializer<'_, UrlQuery<'_>>,
-        key: &str,
-        value: Option<T>,
-    ) {
-        if let Some(val) = value {
-            query.append_pair(key, &val.to_string());
-        }
+impl Endpoint for CreatorsBuilder<'_> {
+    type Response = CommunityResponse;
+
+    fn path(&self) -> Cow<'_, str> {
+        Cow::Owned(format!("{CREATORS_API_BASE}/songs"))
     }
 
-    /// A generic helper to append a slice of values as repeated query parameters.
-    fn append_vec_param<T: ToString>(
-        &self,
-        query: &mut url::form_urlencoded::Serializer<'_, UrlQuery<'_>>,
-        key: &str,
-        values: &[T],
-    ) {
-        for value in values {
-            query.append_pair(key, &value.to_string());
-        }
+    fn cache_mode(&self) -> CacheMode {
+        self.cache_mode
     }
 }
@@ -9,23 +9,23 @@ mod types;
 pub use builder::CreatorsBuilder;
 pub use types::SortBy;
 
-use reqwest::Client;
+use crate::TunecoreClient;
 
 /// A handler for endpoints related to creators.
 pub struct CreatorsEndpoint<'a> {
-    http_client: &'a Client,
+    client: &'a TunecoreClient,
 }
 
 impl<'a> CreatorsEndpoint<'a> {
     /// Creates a new instance of the endpoint handler. (Internal use only)
-    pub(crate) fn new(http_client: &'a Client) -> Self {
-        Self { http_client }
+    pub(crate) fn new(client: &'a TunecoreClient) -> Self {
+        Self { client }
     }
 
     /// Builds a request to fetch community songs.
     ///
     /// Returns a `CreatorsBuilder` to set filters and execute the request.
-    pub fn songs(&self) -> CreatorsBuilder<'_> {
-        CreatorsBuilder::new(self.http_client)
+    pub fn songs(&self) -> CreatorsBuilder<'a> {
+        CreatorsBuilder::new(self.client)
     }
 }
@@ -1,10 +1,17 @@
+use serde::Serialize;
+
 /// Defines the sorting options for community song requests.
-#[derive(Clone, Copy)]
+///
+/// Each variant serializes to the `sort` query value the API expects.
+#[derive(Serialize, Clone, Copy)]
 pub enum SortBy {
     /// Sorts by popularity in descending order.
+    #[serde(rename = "popular_rank:desc")]
     Popularity,
     /// Sorts by the artists' revenue share rate in descending order.
+    #[serde(rename = "share_rate:desc")]
     ShareRateDescending,
     /// Sorts by the artists' revenue share rate in ascending order.
+    #[serde(rename = "share_rate:asc")]
     ShareRateAscending,
 }
@@ -0,0 +1,87 @@
+use crate::{
+    cache::CacheMode, endpoints::Endpoint, error::Error, models::CommunityResponse, TunecoreClient,
+};
+use serde::Serialize;
+use std::borrow::Cow;
+
+// --- Constants ---
+
+/// The path of the favorites listing endpoint.
+const FAVORITES_API_PATH: &str = "api/v2/community/favorites";
+/// The default page number to use for paginated requests.
+const DEFAULT_PAGE: u32 = 1;
+/// The default number of items to request per page.
+const DEFAULT_PER_PAGE: u32 = 100;
+
+// --- Builder ---
+
+/// Builds and executes a request listing the logged-in user's favorite songs.
+///
+/// Favorites are user-specific, so these requests never use the response cache.
+///
+/// # Example
+///
+/// ```no_run
+/// # use tunecore::{Credentials, TunecoreClient};
+/// # async fn run() -> Result<(), tunecore::error::Error> {
+/// let client = TunecoreClient::new();
+/// client.login(&Credentials::new("me@example.com", "secret")).await?;
+///
+/// let response = client.favorites().list().page(1).send().await?;
+///
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize)]
+pub struct FavoritesBuilder<'a> {
+    #[serde(skip)]
+    client: &'a TunecoreClient,
+    page: u32,
+    per_page: u32,
+}
+
+impl<'a> FavoritesBuilder<'a> {
+    /// Creates a new `FavoritesBuilder` with default values.
+    /// This is intended for internal use by the `FavoritesEndpoint`.
+    pub(crate) fn new(client: &'a TunecoreClient) -> Self {
+        Self {
+            client,
+            page: DEFAULT_PAGE,
+            per_page: DEFAULT_PER_PAGE,
+        }
+    }
+
+    // --- Builder Methods ---
+
+    /// Sets the page number for the request.
+    pub fn page(mut self, page: u32) -> Self {
+        self.page = page;
+        self
+    }
+
+    /// Sets the number of results to return per page.
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.per_page = per_page;
+        self
+    }
+
+    /// Executes the request against the API.
+    ///
+    /// This consumes the builder and returns a `Result` containing either
+    /// a `CommunityResponse` on success or an `Error` on failure.
+    pub async fn send(self) -> Result<CommunityResponse, Error> {
+        self.client.send(&self).await
+    }
+}
+
+impl Endpoint for FavoritesBuilder<'_> {
+    type Response = CommunityResponse;
+
+    fn path(&self) -> Cow<'_, str> {
+        Cow::Borrowed(FAVORITES_API_PATH)
+    }
+
+    fn cache_mode(&self) -> CacheMode {
+        CacheMode::Bypass
+    }
+}
@@ -0,0 +1,82 @@
+//! Handles the endpoints for managing the logged-in user's favorite songs.
+//!
+//! This module provides the `FavoritesEndpoint` and the `FavoritesBuilder`
+//! for listing favorites, plus direct methods to add and remove them.
+//! All of these endpoints require a session created with
+//! [`TunecoreClient::login`](crate::TunecoreClient::login).
+
+mod builder;
+
+pub use builder::FavoritesBuilder;
+
+use crate::{endpoints::Endpoint, error::Error, TunecoreClient};
+use reqwest::Method;
+use serde::Serialize;
+use std::borrow::Cow;
+
+/// The base path for song-specific community endpoints.
+const SONGS_API_BASE: &str = "api/v2/community/songs";
+
+/// A handler for endpoints related to favorite songs.
+pub struct FavoritesEndpoint<'a> {
+    client: &'a TunecoreClient,
+}
+
+impl<'a> FavoritesEndpoint<'a> {
+    /// Creates a new instance of the endpoint handler. (Internal use only)
+    pub(crate) fn new(client: &'a TunecoreClient) -> Self {
+        Self { client }
+    }
+
+    /// Builds a request to list the user's favorite songs.
+    ///
+    /// Returns a `FavoritesBuilder` to set pagination and execute the request.
+    pub fn list(&self) -> FavoritesBuilder<'a> {
+        FavoritesBuilder::new(self.client)
+    }
+
+    /// Adds the song with the given ID to the user's favorites.
+    pub async fn add(&self, song_id: u64) -> Result<(), Error> {
+        self.client
+            .send(&FavoriteSong {
+                song_id,
+                method: Method::POST,
+            })
+            .await
+    }
+
+    /// Removes the song with the given ID from the user's favorites.
+    pub async fn remove(&self, song_id: u64) -> Result<(), Error> {
+        self.client
+            .send(&FavoriteSong {
+                song_id,
+                method: Method::DELETE,
+            })
+            .await
+    }
+}
+
+/// Adds (`POST`) or removes (`DELETE`) a song from the user's favorites.
+#[derive(Serialize)]
+struct FavoriteSong {
+    #[serde(skip)]
+    song_id: u64,
+    #[serde(skip)]
+    method: Method,
+}
+
+impl Endpoint for FavoriteSong {
+    type Response = ();
+
+    fn method(&self) -> Method {
+        self.method.clone()
+    }
+
+    fn path(&self) -> Cow<'_, str> {
+        Cow::Owned(format!("{SONGS_API_BASE}/{}/favorite", self.song_id))
+    }
+
+    fn parse_response(&self, _body: &str) -> Result<(), Error> {
+        Ok(())
+    }
+}
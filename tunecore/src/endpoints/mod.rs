@@ -2,5 +2,77 @@
 //!
 //! Each sub-module in this directory corresponds to a group of related
 //! API endpoints (e.g., `creators`).
+//!
+//! Every request is described by a type implementing [`Endpoint`] and sent
+//! through [`TunecoreClient::send`](crate::TunecoreClient::send), which takes
+//! care of URL construction, query encoding, caching and decoding. Adding a
+//! new endpoint only requires declaring its path, query fields and response type.
 
 pub mod creators;
+pub mod favorites;
+
+use crate::{cache::CacheMode, error::Error};
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Serialize};
+use std::borrow::Cow;
+
+/// A declarative description of a single API endpoint.
+///
+/// The implementing type's `Serialize` output becomes the query string:
+/// `None` fields are omitted and sequences are encoded as repeated keys
+/// (e.g. `genre_ids=1&genre_ids=2`). Fields that are not query parameters
+/// should be marked `#[serde(skip)]`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::borrow::Cow;
+/// # use serde::{Deserialize, Serialize};
+/// # use tunecore::{endpoints::Endpoint, TunecoreClient};
+/// #[derive(Deserialize)]
+/// struct Genres {
+///     genres: Vec<String>,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct ListGenres {
+///     locale: &'static str,
+/// }
+///
+/// impl Endpoint for ListGenres {
+///     type Response = Genres;
+///
+///     fn path(&self) -> Cow<'_, str> {
+///         Cow::Borrowed("api/v2/community/genres")
+///     }
+/// }
+///
+/// # async fn run() -> Result<(), tunecore::Error> {
+/// let genres = TunecoreClient::new().send(&ListGenres { locale: "ja" }).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait Endpoint: Serialize {
+    /// The type the response body is decoded into.
+    type Response: DeserializeOwned;
+
+    /// The HTTP method of the endpoint. Defaults to `GET`.
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    /// The path of the endpoint, relative to the client's base URL.
+    fn path(&self) -> Cow<'_, str>;
+
+    /// How the request interacts with the response cache.
+    ///
+    /// Only `GET` requests are ever cached. Defaults to [`CacheMode::Use`].
+    fn cache_mode(&self) -> CacheMode {
+        CacheMode::Use
+    }
+
+    /// Decodes the response body. Defaults to JSON.
+    fn parse_response(&self, body: &str) -> Result<Self::Response, Error> {
+        serde_json::from_str(body).map_err(Error::from)
+    }
+}
@@ -4,14 +4,16 @@
 //! errors that can occur during the library's operation. Using a single
 //! error type allows for easier error handling by the user of the library.
 
+use reqwest::StatusCode;
+use serde_json::Value;
 use thiserror::Error;
 
 /// Represents all possible errors that can occur in this library.
 #[derive(Error, Debug)]
 pub enum Error {
     /// An error occurred during an HTTP request.
-    /// This could be a network error, a timeout, or an HTTP status code
-    /// indicating a client or server error.
+    /// This could be a network error, a timeout, or a failure while
+    /// reading the response body.
     #[error("API request failed: {0}")]
     Request(#[from] reqwest::Error),
 
@@ -27,8 +29,108 @@ pub enum Error {
     #[error("JSON parsing error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// The API rejected the request with a non-success status code.
+    ///
+    /// If the body contained a recognizable JSON error envelope, it is decoded
+    /// into `error`; the raw body is always kept for inspection.
+    #[error("API error (HTTP {status}): {}", describe(.error.as_deref(), .body))]
+    Api {
+        /// The HTTP status code of the response.
+        status: StatusCode,
+        /// The decoded error envelope, if the body contained one.
+        error: Option<Box<ApiError>>,
+        /// The raw response body.
+        body: String,
+    },
+
+    /// An error occurred while encoding an endpoint's query parameters.
+    #[error("Query encoding error: {0}")]
+    QueryEncode(#[from] serde_html_form::ser::Error),
+
+    /// An error occurred while reading from or writing to the response cache.
+    #[error("Cache I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// An error occurred while reading or writing a session file.
+    #[error("Session file I/O error: {0}")]
+    Session(#[source] std::io::Error),
+
     /// An unknown or unexpected error occurred.
     #[error("Unknown error")]
     Unknown,
 }
 
+impl Error {
+    /// Creates an [`Error::Api`] from a rejected response.
+    pub(crate) fn api(status: StatusCode, body: String) -> Self {
+        Self::Api {
+            status,
+            error: ApiError::from_body(&body).map(Box::new),
+            body,
+        }
+    }
+}
+
+/// A structured error body returned by the API for a rejected request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    /// A machine-readable error code, if the API provided one.
+    pub code: Option<String>,
+    /// A human-readable description of the error.
+    pub message: String,
+    /// Any additional information, such as per-field validation errors.
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    /// Tries to decode an error envelope from a response body.
+    ///
+    /// Both flat bodies (`{"code": .., "message": .., "details": ..}`) and
+    /// bodies nested under an `error` key are accepted. Returns `None` for
+    /// empty, non-JSON or unrecognized bodies.
+    pub fn from_body(body: &str) -> Option<Self> {
+        let root: Value = serde_json::from_str(body).ok()?;
+        let envelope = match root.get("error") {
+            Some(nested @ Value::Object(_)) => nested,
+            _ => &root,
+        };
+
+        let message = ["message", "error", "msg"]
+            .iter()
+            .find_map(|key| envelope.get(key)?.as_str())?
+            .to_owned();
+        let code = envelope.get("code").and_then(|code| match code {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        });
+        let details = ["details", "errors"]
+            .iter()
+            .find_map(|key| envelope.get(key))
+            .filter(|details| !details.is_null())
+            .cloned();
+
+        Some(Self {
+            code,
+            message,
+            details,
+        })
+    }
+}
+
+/// The maximum number of characters of a raw body shown in error messages.
+const BODY_PREVIEW_CHARS: usize = 200;
+
+/// Summarizes a rejected response for the `Display` impl of [`Error::Api`].
+fn describe(error: Option<&ApiError>, body: &str) -> String {
+    match error {
+        Some(ApiError {
+            code: Some(code),
+            message,
+            ..
+        }) => format!("[{code}] {message}"),
+        Some(ApiError { message, .. }) => message.clone(),
+        None if body.trim().is_empty() => "<empty body>".to_owned(),
+        None => body.trim().chars().take(BODY_PREVIEW_CHARS).collect(),
+    }
+}
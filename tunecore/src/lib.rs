@@ -30,17 +30,43 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## Cargo features
+//!
+//! - `native-tls` (default): uses the platform TLS implementation.
+//! - `rustls`: uses rustls instead, for builds without system TLS libraries
+//!   such as static musl binaries. Disable default features to drop `native-tls`.
+//! - `chrono` (default): decodes `CommunitySong::street_date` into a
+//!   `chrono::NaiveDate` instead of the `YYYY-MM-DD` string sent by the API.
+//!   Code that builds either way can name the type as [`models::ReleaseDate`].
+//! - `blocking`: enables the `blocking` module, a synchronous client with
+//!   the same builder API for code that does not run an async runtime.
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+pub mod endpoints;
 pub mod error;
 pub mod models;
+pub mod session;
 
-mod endpoints;
-
-pub use endpoints::creators;
-pub use error::Error;
+pub use cache::{CacheMode, ResponseCache};
+pub use endpoints::{creators, favorites, Endpoint};
+pub use error::{ApiError, Error};
+pub use session::{Credentials, Session};
 
+use cache::CacheEntry;
 use creators::CreatorsEndpoint;
-use reqwest::Client;
+use favorites::FavoritesEndpoint;
+use reqwest::{header, Client, Method, RequestBuilder, Response, StatusCode};
+use std::sync::Arc;
+use url::Url;
+
+/// The default host all API requests are sent to.
+const DEFAULT_BASE_URL: &str = "https://www.tunecore.co.jp/";
+/// The path credentials are posted to when logging in.
+const LOGIN_PATH: &str = "api/v2/login";
+
 /// The main entry point for interacting with the Tunecore API.
 ///
 /// This client holds the HTTP client and provides access to different
@@ -48,6 +74,9 @@ use reqwest::Client;
 #[derive(Debug, Clone)]
 pub struct TunecoreClient {
     http_client: Client,
+    cache: Option<Arc<ResponseCache>>,
+    session: Session,
+    base_url: Url,
 }
 
 impl TunecoreClient {
@@ -57,6 +86,9 @@ impl TunecoreClient {
     pub fn new() -> Self {
         Self {
             http_client: Client::new(),
+            cache: None,
+            session: Session::new(),
+            base_url: default_base_url(),
         }
     }
 
@@ -67,15 +99,189 @@ impl TunecoreClient {
     pub fn with_client(client: Client) -> Self {
         Self {
             http_client: client,
+            cache: None,
+            session: Session::new(),
+            base_url: default_base_url(),
         }
     }
 
+    /// Sends all requests to `base_url` instead of the public TuneCore host.
+    ///
+    /// This is mainly useful for pointing the client at a staging
+    /// environment or a local mock server.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Enables the on-disk response cache for every request made by this client.
+    ///
+    /// Individual requests can opt out or force a refresh with their
+    /// `cache_mode` builder method.
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Uses an existing session, e.g. one restored with [`Session::load`].
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Returns the session holding this client's cookies.
+    ///
+    /// Use [`Session::save`] on the returned value to persist a login.
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Logs in to TuneCore with the given credentials.
+    ///
+    /// On success, the session cookies are stored in the client's [`Session`]
+    /// and sent with every subsequent request.
+    pub async fn login(&self, credentials: &Credentials) -> Result<(), Error> {
+        let url = self.endpoint_url(LOGIN_PATH)?;
+        let request = self.request(Method::POST, url).json(credentials);
+        read_body(self.execute(request).await?).await?;
+        Ok(())
+    }
+
     /// Returns a handler for the "creators" API endpoints.
     pub fn creators(&self) -> CreatorsEndpoint<'_> {
-        CreatorsEndpoint::new(&self.http_client)
+        CreatorsEndpoint::new(self)
+    }
+
+    /// Returns a handler for the favorites endpoints.
+    ///
+    /// These endpoints require a logged-in session.
+    pub fn favorites(&self) -> FavoritesEndpoint<'_> {
+        FavoritesEndpoint::new(self)
+    }
+
+    /// Sends a request to any [`Endpoint`] and decodes its response.
+    ///
+    /// This is the single executor behind every endpoint builder: it resolves
+    /// the endpoint path, encodes its query parameters, serves `GET` requests
+    /// through the response cache and decodes the body.
+    pub async fn send<E: Endpoint>(&self, endpoint: &E) -> Result<E::Response, Error> {
+        let mut url = self.endpoint_url(&endpoint.path())?;
+        let query = serde_html_form::to_string(endpoint)?;
+        if !query.is_empty() {
+            url.set_query(Some(&query));
+        }
+
+        let method = endpoint.method();
+        let body = if method == Method::GET {
+            self.get_text(url, endpoint.cache_mode()).await?
+        } else {
+            let request = self.request(method, url);
+            read_body(self.execute(request).await?).await?
+        };
+
+        endpoint.parse_response(&body)
+    }
+
+    /// Resolves an API path against the client's base URL.
+    pub(crate) fn endpoint_url(&self, path: &str) -> Result<Url, Error> {
+        Ok(self.base_url.join(path)?)
+    }
+
+    /// Creates a request that carries the session cookies for `url`.
+    pub(crate) fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let cookies = self.session.cookie_header(&url);
+        let request = self.http_client.request(method, url);
+        match cookies {
+            Some(cookies) => request.header(header::COOKIE, cookies),
+            None => request,
+        }
+    }
+
+    /// Sends a request and records any cookies set by the server.
+    pub(crate) async fn execute(&self, request: RequestBuilder) -> Result<Response, Error> {
+        let response = request.send().await?;
+        self.session.store_cookies(
+            response.headers().get_all(header::SET_COOKIE).iter(),
+            response.url(),
+        );
+        Ok(response)
+    }
+
+    /// Performs a GET request and returns the raw response body.
+    ///
+    /// When a cache is configured, this serves fresh entries from disk,
+    /// revalidates stale ones with conditional headers and stores successful
+    /// responses, all according to `mode`.
+    pub(crate) async fn get_text(&self, url: Url, mode: CacheMode) -> Result<String, Error> {
+        let cache = match (&self.cache, mode) {
+            (Some(cache), CacheMode::Use | CacheMode::Refresh) => cache,
+            _ => {
+                let request = self.request(Method::GET, url);
+                return read_body(self.execute(request).await?).await;
+            }
+        };
+
+        let cookies = self.session.cookie_header(&url);
+        let cached = cache.load(&url, cookies.as_ref()).await;
+        if let Some(entry) = &cached {
+            if mode == CacheMode::Use && cache.is_fresh(entry) {
+                return Ok(entry.body.clone());
+            }
+        }
+
+        let mut request = self.request(Method::GET, url.clone());
+        if let Some(entry) = cached.as_ref().filter(|e| e.can_revalidate()) {
+            if let Some(etag) = &entry.etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = self.execute(request).await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(mut entry) = cached {
+                entry.touch();
+                cache.store(&url, cookies.as_ref(), &entry).await?;
+                return Ok(entry.body);
+            }
+        }
+
+        let header_value = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v: &header::HeaderValue| v.to_str().ok())
+                .map(str::to_owned)
+        };
+        let etag = header_value(header::ETAG);
+        let last_modified = header_value(header::LAST_MODIFIED);
+        let body = read_body(response).await?;
+
+        let entry = CacheEntry::new(&url, etag, last_modified, body);
+        cache.store(&url, cookies.as_ref(), &entry).await?;
+        Ok(entry.body)
+    }
+}
+
+/// Reads a response body, turning non-success statuses into [`Error::Api`].
+async fn read_body(response: Response) -> Result<String, Error> {
+    let status = response.status();
+    let body = response.text().await?;
+    if status.is_success() {
+        Ok(body)
+    } else {
+        Err(Error::api(status, body))
     }
 }
 
+/// Returns the URL of the public TuneCore host.
+fn default_base_url() -> Url {
+    Url::parse(DEFAULT_BASE_URL).expect("default base URL is a valid URL")
+}
+
 impl Default for TunecoreClient {
     /// Creates a default `TunecoreClient`.
     ///
@@ -15,4 +15,4 @@ pub mod song;
 // Re-export the primary models to the top level of the `models` module.
 pub use artist::{Artist, ArtistName};
 pub use response::CommunityResponse;
-pub use song::{CommunitySong, SongTitle};
+pub use song::{CommunitySong, ReleaseDate, SongTitle};
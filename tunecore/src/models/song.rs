@@ -1,7 +1,16 @@
 use super::artist::{Artist, ArtistName};
-use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
+/// The type of [`CommunitySong::street_date`], for code that builds with and
+/// without the `chrono` feature.
+#[cfg(feature = "chrono")]
+pub type ReleaseDate = chrono::NaiveDate;
+
+/// The type of [`CommunitySong::street_date`], for code that builds with and
+/// without the `chrono` feature.
+#[cfg(not(feature = "chrono"))]
+pub type ReleaseDate = String;
+
 /// Represents the localized titles for a song.
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct SongTitle {
@@ -37,7 +46,11 @@ pub struct CommunitySong {
     /// The URL to the album/song cover art.
     pub jacket_url: String,
     /// The release date of the song.
-    pub street_date: NaiveDate,
+    #[cfg(feature = "chrono")]
+    pub street_date: chrono::NaiveDate,
+    /// The release date of the song, as the `YYYY-MM-DD` string sent by the API.
+    #[cfg(not(feature = "chrono"))]
+    pub street_date: String,
     /// The localized titles of the song.
     pub song_title: SongTitle,
     /// The localized names of the primary artist.
@@ -49,3 +62,21 @@ pub struct CommunitySong {
     /// Indicates if the current user has favorited this song.
     pub is_favorite: bool,
 }
+
+impl CommunitySong {
+    /// Parses [`channel_share_percent_str`](Self::channel_share_percent_str) into a number.
+    ///
+    /// Accepts values such as `"70"`, `"70.5"` or `"70%"`.
+    ///
+    /// # Returns
+    /// The share rate in percent, or `None` if the string is not a finite number.
+    pub fn channel_share_percent(&self) -> Option<f64> {
+        self.channel_share_percent_str
+            .trim()
+            .trim_end_matches('%')
+            .trim_end()
+            .parse()
+            .ok()
+            .filter(|percent: &f64| percent.is_finite())
+    }
+}
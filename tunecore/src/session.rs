@@ -0,0 +1,151 @@
+//! Authenticated session support.
+//!
+//! A [`Session`] is a cookie jar shared by every clone of a `TunecoreClient`.
+//! Cookies set by the server (e.g. after [`TunecoreClient::login`]) are sent
+//! back on subsequent requests, and the session can be persisted to a file so
+//! that tools do not have to log in on every run. Cookies keep the domain,
+//! path, expiry and security attributes they were set with, so a restored
+//! session behaves exactly like the one that was saved.
+//!
+//! [`TunecoreClient::login`]: crate::TunecoreClient::login
+
+use crate::error::Error;
+use cookie_store::{Cookie, CookieStore};
+use reqwest::header::HeaderValue;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
+use url::Url;
+
+/// The credentials used to log in to a TuneCore account.
+#[derive(Serialize, Clone)]
+pub struct Credentials {
+    /// The e-mail address of the account.
+    pub email: String,
+    /// The password of the account.
+    pub password: String,
+}
+
+impl Credentials {
+    /// Creates a new set of credentials.
+    pub fn new(email: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            email: email.into(),
+            password: password.into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Credentials {
+    /// Formats the credentials without revealing the password.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("email", &self.email)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// A cookie jar holding the state of an authenticated session.
+///
+/// Cloning a `Session` is cheap and yields a handle to the same jar.
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    store: Arc<RwLock<CookieStore>>,
+}
+
+impl Session {
+    /// Creates a new, empty session.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a session previously written with [`Session::save`].
+    ///
+    /// Cookies that expired since the session was saved are dropped.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let bytes = tokio::fs::read(path).await.map_err(Error::Session)?;
+        let cookies: Vec<Cookie<'static>> = serde_json::from_slice(&bytes)?;
+        let store = CookieStore::from_cookies(cookies.into_iter().map(Ok::<_, Infallible>), false)
+            .unwrap_or_else(|never| match never {});
+        Ok(Self {
+            store: Arc::new(RwLock::new(store)),
+        })
+    }
+
+    /// Writes the session cookies, with all their attributes, to `path` so
+    /// they can be restored later.
+    ///
+    /// The file contains live credentials, so on Unix it is only readable
+    /// and writable by its owner.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let bytes = {
+            let store = self.store.read().unwrap_or_else(PoisonError::into_inner);
+            let cookies: Vec<&Cookie<'static>> = store.iter_unexpired().collect();
+            serde_json::to_vec_pretty(&cookies)?
+        };
+        write_private(path.as_ref(), &bytes)
+            .await
+            .map_err(Error::Session)
+    }
+
+    /// Returns `true` if the session does not hold any unexpired cookies.
+    pub fn is_empty(&self) -> bool {
+        let store = self.store.read().unwrap_or_else(PoisonError::into_inner);
+        let is_empty = store.iter_unexpired().next().is_none();
+        is_empty
+    }
+
+    /// Returns the `Cookie` header value to send to `url`, if any.
+    pub(crate) fn cookie_header(&self, url: &Url) -> Option<HeaderValue> {
+        let store = self.store.read().unwrap_or_else(PoisonError::into_inner);
+        let header = store
+            .get_request_values(url)
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+
+    /// Stores the cookies from a set of `Set-Cookie` response headers.
+    ///
+    /// Malformed cookies and cookies the server may not set for `url` are ignored.
+    pub(crate) fn store_cookies<'h>(
+        &self,
+        headers: impl Iterator<Item = &'h HeaderValue>,
+        url: &Url,
+    ) {
+        let mut headers = headers.filter_map(|header| header.to_str().ok()).peekable();
+        if headers.peek().is_none() {
+            return;
+        }
+        let mut store = self.store.write().unwrap_or_else(PoisonError::into_inner);
+        for header in headers {
+            store.parse(header, url).ok();
+        }
+    }
+}
+
+/// Writes `bytes` to `path`, creating or truncating a file only its owner may access.
+async fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+    #[cfg(unix)]
+    {
+        // The mode only applies to new files, so also restrict existing ones.
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .await?;
+    }
+    file.write_all(bytes).await?;
+    file.flush().await
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{MockResponse, MockServer};
+use tunecore::{ApiError, Error, TunecoreClient};
+
+/// Sends a songs request to a server answering with `response` and returns the error.
+async fn songs_error(response: MockResponse) -> Error {
+    let server = MockServer::start(vec![response]);
+    TunecoreClient::new()
+        .with_base_url(server.base_url())
+        .creators()
+        .songs()
+        .send()
+        .await
+        .expect_err("request should fail")
+}
+
+#[tokio::test]
+async fn json_error_body_is_decoded() {
+    let body = r#"{"code":"invalid_parameter","message":"per_page must be <= 100","details":{"per_page":1000}}"#;
+    let err = songs_error(MockResponse::new(400, "application/json", body)).await;
+
+    let Error::Api {
+        status,
+        error,
+        body: raw,
+    } = &err
+    else {
+        panic!("expected Error::Api, got {err:?}");
+    };
+    assert_eq!(status.as_u16(), 400);
+    assert_eq!(raw, body);
+    assert_eq!(
+        error.as_deref(),
+        Some(&ApiError {
+            code: Some("invalid_parameter".to_owned()),
+            message: "per_page must be <= 100".to_owned(),
+            details: Some(serde_json::json!({ "per_page": 1000 })),
+        })
+    );
+    assert_eq!(
+        err.to_string(),
+        "API error (HTTP 400 Bad Request): [invalid_parameter] per_page must be <= 100"
+    );
+}
+
+#[tokio::test]
+async fn nested_json_error_body_is_decoded() {
+    let body = r#"{"error":{"code":404,"message":"Not Found"}}"#;
+    let err = songs_error(MockResponse::new(404, "application/json", body)).await;
+
+    let Error::Api { error, .. } = err else {
+        panic!("expected Error::Api, got {err:?}");
+    };
+    let error = error.expect("envelope should be decoded");
+    assert_eq!(error.code.as_deref(), Some("404"));
+    assert_eq!(error.message, "Not Found");
+    assert_eq!(error.details, None);
+}
+
+#[tokio::test]
+async fn html_error_page_keeps_raw_body() {
+    let body = "<html><body><h1>502 Bad Gateway</h1></body></html>";
+    let err = songs_error(MockResponse::new(502, "text/html", body)).await;
+
+    let Error::Api {
+        status,
+        error,
+        body: raw,
+    } = &err
+    else {
+        panic!("expected Error::Api, got {err:?}");
+    };
+    assert_eq!(status.as_u16(), 502);
+    assert_eq!(error, &None);
+    assert_eq!(raw, body);
+    assert!(err.to_string().ends_with(body));
+}
+
+#[tokio::test]
+async fn empty_error_body_is_reported() {
+    let err = songs_error(MockResponse::new(503, "text/plain", "")).await;
+
+    let Error::Api {
+        status,
+        error,
+        body,
+    } = &err
+    else {
+        panic!("expected Error::Api, got {err:?}");
+    };
+    assert_eq!(status.as_u16(), 503);
+    assert_eq!(error, &None);
+    assert!(body.is_empty());
+    assert_eq!(
+        err.to_string(),
+        "API error (HTTP 503 Service Unavailable): <empty body>"
+    );
+}
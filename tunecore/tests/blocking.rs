@@ -0,0 +1,30 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{MockResponse, MockServer, COMMUNITY_SONGS};
+use tunecore::blocking::{creators::SortBy, TunecoreClient};
+
+#[test]
+fn songs_request_encodes_filters_and_decodes_fixture() {
+    let server = MockServer::start(vec![MockResponse::json(COMMUNITY_SONGS)]);
+    let client = TunecoreClient::new().with_base_url(server.base_url());
+
+    let response = client
+        .creators()
+        .songs()
+        .page(2)
+        .per_page(10)
+        .genre_ids(&[3, 7])
+        .sort(SortBy::Popularity)
+        .send()
+        .unwrap();
+
+    assert_eq!(
+        server.next_request(),
+        "GET /api/v2/community/songs?page=2&per_page=10&genre_ids=3&genre_ids=7&sort=popular_rank%3Adesc HTTP/1.1"
+    );
+    assert_eq!(response.total, 2);
+    assert_eq!(response.community_songs[0].id, 1001);
+    assert_eq!(response.community_songs[1].song_title.ja, "海辺");
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use common::{scratch_dir, MockResponse, MockServer, COMMUNITY_SONGS};
+use std::time::Duration;
+use tunecore::{CacheMode, Credentials, ResponseCache, TunecoreClient};
+
+/// Returns the fixture with the ID of its first song replaced, so responses can be told apart.
+fn songs_with_first_id(id: u64) -> String {
+    COMMUNITY_SONGS.replacen("\"id\": 1001", &format!("\"id\": {id}"), 1)
+}
+
+#[tokio::test]
+async fn fresh_entries_are_served_without_a_request() {
+    let server = MockServer::start(vec![MockResponse::json(COMMUNITY_SONGS)]);
+    let cache = ResponseCache::new(scratch_dir("cache-fresh"), Duration::from_secs(3600));
+    let client = TunecoreClient::new()
+        .with_base_url(server.base_url())
+        .with_cache(cache);
+
+    let first = client.creators().songs().send().await.unwrap();
+    // The server only answers once, so this succeeds only if served from the cache.
+    let second = client.creators().songs().send().await.unwrap();
+
+    assert_eq!(first.community_songs[0].id, 1001);
+    assert_eq!(second.community_songs[0].id, 1001);
+}
+
+#[tokio::test]
+async fn stale_entries_are_revalidated_with_their_etag() {
+    let server = MockServer::start(vec![
+        MockResponse::json(COMMUNITY_SONGS).header("ETag", "\"v1\""),
+        MockResponse::not_modified(),
+    ]);
+    let cache = ResponseCache::new(scratch_dir("cache-stale"), Duration::ZERO);
+    let client = TunecoreClient::new()
+        .with_base_url(server.base_url())
+        .with_cache(cache);
+
+    client.creators().songs().send().await.unwrap();
+    let revalidated = client.creators().songs().send().await.unwrap();
+
+    assert_eq!(server.next_full_request().header("if-none-match"), None);
+    assert_eq!(
+        server.next_full_request().header("if-none-match"),
+        Some("\"v1\"")
+    );
+    assert_eq!(revalidated.community_songs[0].id, 1001);
+}
+
+#[tokio::test]
+async fn refresh_mode_ignores_fresh_entries() {
+    let server = MockServer::start(vec![
+        MockResponse::json(COMMUNITY_SONGS),
+        MockResponse::json(&songs_with_first_id(2001)),
+    ]);
+    let cache = ResponseCache::new(scratch_dir("cache-refresh"), Duration::from_secs(3600));
+    let client = TunecoreClient::new()
+        .with_base_url(server.base_url())
+        .with_cache(cache);
+
+    client.creators().songs().send().await.unwrap();
+    let refreshed = client
+        .creators()
+        .songs()
+        .cache_mode(CacheMode::Refresh)
+        .send()
+        .await
+        .unwrap();
+    let cached = client.creators().songs().send().await.unwrap();
+
+    assert_eq!(refreshed.community_songs[0].id, 2001);
+    assert_eq!(cached.community_songs[0].id, 2001);
+}
+
+#[tokio::test]
+async fn bypass_mode_neither_reads_nor_writes_the_cache() {
+    let server = MockServer::start(vec![MockResponse::json(COMMUNITY_SONGS)]);
+    let dir = scratch_dir("cache-bypass");
+    let cache = ResponseCache::new(&dir, Duration::from_secs(3600));
+    let client = TunecoreClient::new()
+        .with_base_url(server.base_url())
+        .with_cache(cache);
+
+    client
+        .creators()
+        .songs()
+        .cache_mode(CacheMode::Bypass)
+        .send()
+        .await
+        .unwrap();
+
+    assert!(!dir.exists());
+}
+
+#[tokio::test]
+async fn entries_are_not_shared_between_sessions() {
+    let server = MockServer::start(vec![
+        MockResponse::json("{}").header("Set-Cookie", "session=abc; Path=/"),
+        MockResponse::json(COMMUNITY_SONGS),
+        MockResponse::json(&songs_with_first_id(2001)),
+    ]);
+    let dir = scratch_dir("cache-sessions");
+    let ttl = Duration::from_secs(3600);
+    let logged_in = TunecoreClient::new()
+        .with_base_url(server.base_url())
+        .with_cache(ResponseCache::new(&dir, ttl));
+    let anonymous = TunecoreClient::new()
+        .with_base_url(server.base_url())
+        .with_cache(ResponseCache::new(&dir, ttl));
+
+    logged_in
+        .login(&Credentials::new("user@example.com", "secret"))
+        .await
+        .unwrap();
+    let personal = logged_in.creators().songs().send().await.unwrap();
+    let public = anonymous.creators().songs().send().await.unwrap();
+
+    assert_eq!(personal.community_songs[0].id, 1001);
+    assert_eq!(public.community_songs[0].id, 2001);
+}
@@ -0,0 +1,170 @@
+//! Shared fixtures and a minimal HTTP server for the client tests.
+
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use url::Url;
+
+/// A `CommunityResponse` body with two songs.
+pub const COMMUNITY_SONGS: &str = include_str!("../fixtures/community_songs.json");
+
+/// A canned HTTP response served by [`MockServer`].
+pub struct MockResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    /// A response with an arbitrary status, content type and body.
+    pub fn new(status: u16, content_type: &'static str, body: &str) -> Self {
+        Self {
+            status,
+            content_type,
+            headers: Vec::new(),
+            body: body.to_owned(),
+        }
+    }
+
+    /// A `200 OK` response with a JSON body.
+    pub fn json(body: &str) -> Self {
+        Self::new(200, "application/json", body)
+    }
+
+    /// A `304 Not Modified` response without a body.
+    pub fn not_modified() -> Self {
+        Self::new(304, "application/json", "")
+    }
+
+    /// Adds a header to the response.
+    pub fn header(mut self, name: &'static str, value: &str) -> Self {
+        self.headers.push((name, value.to_owned()));
+        self
+    }
+}
+
+/// A request received by [`MockServer`].
+#[derive(Debug)]
+pub struct MockRequest {
+    /// The request line, e.g. `GET /path HTTP/1.1`.
+    pub line: String,
+    /// The headers as `(lowercase name, value)` pairs.
+    pub headers: Vec<(String, String)>,
+    /// The request body.
+    pub body: String,
+}
+
+impl MockRequest {
+    /// Returns the value of the first header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A single-threaded HTTP server answering requests with canned responses.
+pub struct MockServer {
+    base_url: Url,
+    requests: Receiver<MockRequest>,
+}
+
+impl MockServer {
+    /// Starts a server that answers one request per entry in `responses`, in order.
+    ///
+    /// The listener is closed once every response has been served, so any
+    /// further request fails to connect.
+    pub fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let addr = listener.local_addr().expect("mock server address");
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().expect("accept connection");
+                let mut reader = BufReader::new(stream.try_clone().expect("clone stream"));
+
+                let mut request_line = String::new();
+                reader
+                    .read_line(&mut request_line)
+                    .expect("read request line");
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("read header");
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_owned()));
+                    }
+                }
+                let length = headers
+                    .iter()
+                    .find(|(name, _)| name == "content-length")
+                    .and_then(|(_, value)| value.parse().ok())
+                    .unwrap_or(0);
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).expect("read body");
+                tx.send(MockRequest {
+                    line: request_line.trim_end().to_owned(),
+                    headers,
+                    body: String::from_utf8_lossy(&body).into_owned(),
+                })
+                .ok();
+
+                let extra_headers: String = response
+                    .headers
+                    .iter()
+                    .map(|(name, value)| format!("{name}: {value}\r\n"))
+                    .collect();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+                    response.status,
+                    response.content_type,
+                    response.body.len(),
+                    extra_headers,
+                    response.body
+                )
+                .expect("write response");
+            }
+        });
+
+        Self {
+            base_url: Url::parse(&format!("http://{addr}/")).expect("mock server URL"),
+            requests: rx,
+        }
+    }
+
+    /// The URL to pass to `TunecoreClient::with_base_url`.
+    pub fn base_url(&self) -> Url {
+        self.base_url.clone()
+    }
+
+    /// Returns the request line (e.g. `GET /path HTTP/1.1`) of the next request served.
+    pub fn next_request(&self) -> String {
+        self.next_full_request().line
+    }
+
+    /// Returns the next request served, including its headers and body.
+    pub fn next_full_request(&self) -> MockRequest {
+        self.requests
+            .recv()
+            .expect("mock server received a request")
+    }
+}
+
+/// Returns an empty scratch directory named after the test using it.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tunecore-{}-{name}", std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    dir
+}
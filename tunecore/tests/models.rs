@@ -0,0 +1,31 @@
+mod common;
+
+use common::COMMUNITY_SONGS;
+use tunecore::models::{CommunityResponse, CommunitySong};
+
+fn song_with_share(share: &str) -> CommunitySong {
+    let response: CommunityResponse = serde_json::from_str(COMMUNITY_SONGS).unwrap();
+    let mut song = response.community_songs.into_iter().next().unwrap();
+    song.channel_share_percent_str = share.to_owned();
+    song
+}
+
+#[test]
+fn channel_share_percent_accepts_numbers_and_percent_signs() {
+    assert_eq!(song_with_share("70").channel_share_percent(), Some(70.0));
+    assert_eq!(
+        song_with_share(" 70.5 % ").channel_share_percent(),
+        Some(70.5)
+    );
+}
+
+#[test]
+fn channel_share_percent_rejects_non_finite_values() {
+    for share in ["NaN", "inf", "-infinity", "", "seventy"] {
+        assert_eq!(
+            song_with_share(share).channel_share_percent(),
+            None,
+            "{share}"
+        );
+    }
+}
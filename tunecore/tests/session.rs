@@ -0,0 +1,120 @@
+mod common;
+
+use common::{scratch_dir, MockResponse, MockServer, COMMUNITY_SONGS};
+use tunecore::{Credentials, Session, TunecoreClient};
+
+/// A `Set-Cookie` header for a session cookie scoped to the API.
+const SESSION_COOKIE: &str = "session=abc123; Path=/api; HttpOnly; Max-Age=3600";
+
+#[tokio::test]
+async fn login_posts_credentials_and_stores_the_session_cookie() {
+    let server = MockServer::start(vec![
+        MockResponse::json("{}").header("Set-Cookie", SESSION_COOKIE),
+        MockResponse::json(COMMUNITY_SONGS),
+    ]);
+    let client = TunecoreClient::new().with_base_url(server.base_url());
+
+    client
+        .login(&Credentials::new("user@example.com", "secret"))
+        .await
+        .unwrap();
+    client.favorites().list().send().await.unwrap();
+
+    let login = server.next_full_request();
+    assert_eq!(login.line, "POST /api/v2/login HTTP/1.1");
+    assert_eq!(
+        login.body,
+        r#"{"email":"user@example.com","password":"secret"}"#
+    );
+    assert!(!client.session().is_empty());
+
+    let favorites = server.next_full_request();
+    assert!(favorites
+        .line
+        .starts_with("GET /api/v2/community/favorites"));
+    assert_eq!(favorites.header("cookie"), Some("session=abc123"));
+}
+
+#[tokio::test]
+async fn favorites_are_changed_with_the_session_cookie() {
+    let server = MockServer::start(vec![
+        MockResponse::json("{}").header("Set-Cookie", SESSION_COOKIE),
+        MockResponse::json("{}"),
+        MockResponse::json("{}"),
+    ]);
+    let client = TunecoreClient::new().with_base_url(server.base_url());
+
+    client
+        .login(&Credentials::new("user@example.com", "secret"))
+        .await
+        .unwrap();
+    client.favorites().add(1001).await.unwrap();
+    client.favorites().remove(1001).await.unwrap();
+
+    server.next_full_request();
+    let add = server.next_full_request();
+    assert_eq!(
+        add.line,
+        "POST /api/v2/community/songs/1001/favorite HTTP/1.1"
+    );
+    assert_eq!(add.header("cookie"), Some("session=abc123"));
+    let remove = server.next_full_request();
+    assert_eq!(
+        remove.line,
+        "DELETE /api/v2/community/songs/1001/favorite HTTP/1.1"
+    );
+    assert_eq!(remove.header("cookie"), Some("session=abc123"));
+}
+
+#[tokio::test]
+async fn saved_sessions_keep_cookie_attributes() {
+    let server = MockServer::start(vec![
+        MockResponse::json("{}").header("Set-Cookie", SESSION_COOKIE),
+        MockResponse::json(COMMUNITY_SONGS),
+    ]);
+    let path = scratch_dir("session-round-trip").with_extension("json");
+    let client = TunecoreClient::new().with_base_url(server.base_url());
+    client
+        .login(&Credentials::new("user@example.com", "secret"))
+        .await
+        .unwrap();
+
+    client.session().save(&path).await.unwrap();
+    let restored = TunecoreClient::new()
+        .with_base_url(server.base_url())
+        .with_session(Session::load(&path).await.unwrap());
+    restored.favorites().list().send().await.unwrap();
+
+    server.next_full_request();
+    assert_eq!(
+        server.next_full_request().header("cookie"),
+        Some("session=abc123")
+    );
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.contains("HttpOnly"), "attributes are kept: {saved}");
+    assert!(saved.contains("/api"), "the path is kept: {saved}");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[tokio::test]
+async fn cookies_are_only_sent_within_their_path() {
+    let server = MockServer::start(vec![
+        MockResponse::json("{}").header("Set-Cookie", "scoped=1; Path=/api/v2/login"),
+        MockResponse::json(COMMUNITY_SONGS),
+    ]);
+    let client = TunecoreClient::new().with_base_url(server.base_url());
+
+    client
+        .login(&Credentials::new("user@example.com", "secret"))
+        .await
+        .unwrap();
+    client.creators().songs().send().await.unwrap();
+
+    server.next_full_request();
+    assert_eq!(server.next_full_request().header("cookie"), None);
+}